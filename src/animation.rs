@@ -0,0 +1,692 @@
+use gltf::{animation::Channel, animation::Interpolation, animation::util::ReadOutputs, buffer::Data};
+use std::collections::{BTreeMap, HashMap};
+use glam::{Mat4, Quat, Vec3, Vec4};
+use ordered_float::OrderedFloat;
+
+// cubic spline keyframes carry the tangents on either side of them, the others leave them at zero
+#[derive(Debug, Clone)]
+struct Keyframe {
+    in_tangent: Transformation,
+    value: Transformation,
+    out_tangent: Transformation,
+}
+
+#[derive(Debug)]
+pub struct Animation {
+    pub target: usize,
+    interpolation: Interpolation,
+    map: BTreeMap<OrderedFloat<f32>, Keyframe>,
+}
+
+impl Animation {
+    pub fn new(channel: Channel, buffers: &[Data]) -> Self {
+        let target = channel.target().node().index();
+        let interpolation = channel.sampler().interpolation();
+        let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+
+        let inputs = reader.read_inputs().unwrap().collect::<Vec<_>>();
+        let outputs: Vec<Transformation> = match reader.read_outputs().unwrap() {
+            ReadOutputs::Translations(ts) => ts.map(|t| Transformation::Translate(t.into())).collect(),
+            ReadOutputs::Rotations(rs) => rs.into_f32().map(|r| Transformation::Rotate(Quat::from_slice(&r))).collect(),
+            ReadOutputs::Scales(ss) => ss.map(|s| Transformation::Scale(s.into())).collect(),
+            ReadOutputs::MorphTargetWeights(ws) => {
+                // one weight per morph target for every output, all in one flat list
+                let ws = ws.into_f32().collect::<Vec<_>>();
+                let outputs_per_input = if matches!(interpolation, Interpolation::CubicSpline) { 3 } else { 1 };
+                let targets = (ws.len() / (inputs.len() * outputs_per_input).max(1)).max(1);
+                ws.chunks(targets).map(|w| Transformation::Weights(w.to_vec())).collect()
+            },
+        };
+        Self::from_keyframes(target, interpolation, &inputs, &outputs)
+    }
+
+    // outputs are laid out like the glTF sampler's: one value per input, or for cubic splines
+    // an (in tangent, value, out tangent) triple per input
+    pub fn from_keyframes(target: usize, interpolation: Interpolation, inputs: &[f32], outputs: &[Transformation]) -> Self {
+        let map = match interpolation {
+            Interpolation::CubicSpline => inputs.iter().zip(outputs.chunks_exact(3)).map(|(&t, o)| {
+                (OrderedFloat(t), Keyframe { in_tangent: o[0].clone(), value: o[1].clone(), out_tangent: o[2].clone() })
+            }).collect(),
+            Interpolation::Linear | Interpolation::Step => inputs.iter().zip(outputs).map(|(&t, value)| {
+                let zero = value.zero();
+                (OrderedFloat(t), Keyframe { in_tangent: zero.clone(), value: value.clone(), out_tangent: zero })
+            }).collect(),
+        };
+        Self {
+            target,
+            map,
+            interpolation,
+        }
+    }
+
+    // times of the first and last keyframes
+    pub fn range(&self) -> Option<(f32, f32)> {
+        Some((self.map.keys().next()?.0, self.map.keys().next_back()?.0))
+    }
+
+    // time is in the sampler's own timeline, clips take care of looping
+    pub fn get(&self, local_time: f32) -> Option<Transformation> {
+        // before the first keyframe or after the last one, the animation holds still
+        let (&OrderedFloat(prev_time), prev) = self.map.range(..=OrderedFloat(local_time)).next_back().or_else(|| self.map.iter().next())?;
+        let (&OrderedFloat(next_time), next) = match self.map.range(OrderedFloat(local_time)..).find(|(&OrderedFloat(t), _)| t > prev_time) {
+            Some(next) => next,
+            None => return Some(prev.value.clone()),
+        };
+        let interp_time = ((local_time - prev_time) / (next_time - prev_time)).clamp(0.0, 1.0);
+
+        Some(match self.interpolation {
+            Interpolation::Step => prev.value.clone(),
+            Interpolation::Linear => match (&prev.value, &next.value) {
+                (Transformation::Rotate(q0), Transformation::Rotate(q1)) => {
+                    let q1 = if q0.dot(*q1) < 0.0 { -*q1 } else { *q1 };
+                    Transformation::Rotate(q0.slerp(q1, interp_time).normalize())
+                }
+                (v0, v1) => Transformation::weighted_sum(&[(v0, 1.0 - interp_time), (v1, interp_time)]),
+            },
+            Interpolation::CubicSpline => {
+                // hermite basis, the tangents are scaled by the time between the keyframes
+                let t = interp_time;
+                let (t2, t3) = (t * t, t * t * t);
+                let delta = next_time - prev_time;
+                Transformation::weighted_sum(&[
+                    (&prev.value, 2.0 * t3 - 3.0 * t2 + 1.0),
+                    (&prev.out_tangent, delta * (t3 - 2.0 * t2 + t)),
+                    (&next.value, -2.0 * t3 + 3.0 * t2),
+                    (&next.in_tangent, delta * (t3 - t2)),
+                ])
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaybackMode {
+    Loop,
+    // plays through once and holds the last frame
+    Once,
+    // plays forwards then backwards
+    PingPong,
+}
+
+impl PlaybackMode {
+    pub fn next(self) -> Self {
+        match self {
+            PlaybackMode::Loop => PlaybackMode::Once,
+            PlaybackMode::Once => PlaybackMode::PingPong,
+            PlaybackMode::PingPong => PlaybackMode::Loop,
+        }
+    }
+}
+
+impl std::fmt::Display for PlaybackMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            PlaybackMode::Loop => "loop",
+            PlaybackMode::Once => "once",
+            PlaybackMode::PingPong => "ping-pong",
+        })
+    }
+}
+
+// one glTF animation, its channels only ever play together
+#[derive(Debug)]
+pub struct AnimationClip {
+    pub name: String,
+    // earliest and latest keyframe over every channel, in the file's timeline
+    pub start: f32,
+    pub end: f32,
+    pub mode: PlaybackMode,
+    pub speed: f32,
+    pub channels: Vec<Animation>,
+}
+
+impl AnimationClip {
+    pub fn from_gltf(animation: &gltf::Animation, buffers: &[Data]) -> Self {
+        let name = animation.name().map_or_else(|| format!("animation {}", animation.index()), str::to_string);
+        Self::new(name, animation.channels().map(|c| Animation::new(c, buffers)).collect())
+    }
+
+    pub fn new(name: String, channels: Vec<Animation>) -> Self {
+        let (start, end) = channels.iter()
+            .filter_map(Animation::range)
+            .reduce(|a, b| (a.0.min(b.0), a.1.max(b.1)))
+            .unwrap_or((0.0, 0.0));
+        Self {
+            name,
+            start,
+            end,
+            mode: PlaybackMode::Loop,
+            speed: 1.0,
+            channels,
+        }
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.end - self.start
+    }
+
+    // changes speed by step, going over 0 rather than stopping there, since a stopped clip looks like a hang.
+    // negative speeds play the clip in reverse
+    pub fn step_speed(&mut self, step: f32) {
+        self.speed += step;
+        if self.speed.abs() < step.abs() / 2.0 {
+            self.speed += step;
+        }
+    }
+
+    // maps time since the clip started playing onto the clip's keyframe timeline. with a negative speed it plays
+    // backwards, once from the end to the start
+    pub fn local_time(&self, time: f32) -> f32 {
+        let duration = self.duration();
+        if duration <= 0.0 {
+            return self.start;
+        }
+        let time = time * self.speed;
+        let offset = match self.mode {
+            PlaybackMode::Loop => time.rem_euclid(duration),
+            PlaybackMode::Once if self.speed < 0.0 => (duration + time).clamp(0.0, duration),
+            PlaybackMode::Once => time.clamp(0.0, duration),
+            PlaybackMode::PingPong => duration - (time.rem_euclid(2.0 * duration) - duration).abs(),
+        };
+        self.start + offset
+    }
+
+    // every animated node's transformation at the given playback time
+    pub fn sample(&self, time: f32) -> impl Iterator<Item = (usize, Transformation)> + '_ {
+        let local_time = self.local_time(time);
+        self.channels.iter().filter_map(move |c| c.get(local_time).map(|t| (c.target, t)))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Transformation {
+    Scale(Vec3),
+    Translate(Vec3),
+    Rotate(Quat),
+    // morph target weights, one per target of the node's mesh
+    Weights(Vec<f32>),
+}
+
+impl Transformation {
+    fn zero(&self) -> Self {
+        match self {
+            Transformation::Scale(_) => Transformation::Scale(Vec3::ZERO),
+            Transformation::Translate(_) => Transformation::Translate(Vec3::ZERO),
+            Transformation::Rotate(_) => Transformation::Rotate(Quat::from_xyzw(0.0, 0.0, 0.0, 0.0)),
+            Transformation::Weights(w) => Transformation::Weights(vec![0.0; w.len()]),
+        }
+    }
+
+    fn components(&self) -> Vec<f32> {
+        match self {
+            Transformation::Scale(v) | Transformation::Translate(v) => v.to_array().to_vec(),
+            Transformation::Rotate(q) => Vec4::from(*q).to_array().to_vec(),
+            Transformation::Weights(w) => w.clone(),
+        }
+    }
+
+    // the same kind of transformation with new components, rotations come out normalized
+    fn with_components(&self, c: &[f32]) -> Self {
+        match self {
+            Transformation::Scale(_) => Transformation::Scale(Vec3::from_slice(c)),
+            Transformation::Translate(_) => Transformation::Translate(Vec3::from_slice(c)),
+            Transformation::Rotate(_) => Transformation::Rotate(Quat::from_slice(c).normalize()),
+            Transformation::Weights(_) => Transformation::Weights(c.to_vec()),
+        }
+    }
+
+    // every term's components times its factor, added up into the first term's kind of transformation
+    fn weighted_sum(terms: &[(&Transformation, f32)]) -> Self {
+        let mut sum = vec![0.0; terms[0].0.components().len()];
+        for (transformation, factor) in terms {
+            for (s, c) in sum.iter_mut().zip(transformation.components()) {
+                *s += c * factor;
+            }
+        }
+        terms[0].0.with_components(&sum)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationBlend {
+    // normalized lerp, cheaper and close enough for nearby poses
+    Nlerp,
+    Slerp,
+}
+
+// a node's local transform split up so it can be animated and blended component by component
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeTransform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+    // morph target weights for the node's mesh, empty without one
+    pub weights: Vec<f32>,
+}
+
+impl NodeTransform {
+    pub const IDENTITY: Self = Self { translation: Vec3::ZERO, rotation: Quat::IDENTITY, scale: Vec3::ONE, weights: Vec::new() };
+
+    pub fn from_gltf(node: &gltf::Node) -> Self {
+        let (translation, rotation, scale) = node.transform().decomposed();
+        // the node's weights win over the mesh's defaults, and both are optional
+        let targets = node.mesh().and_then(|m| m.primitives().next()).map_or(0, |p| p.morph_targets().len());
+        let weights = node.weights()
+            .or_else(|| node.mesh().and_then(|m| m.weights()))
+            .map_or_else(|| vec![0.0; targets], |w| w.to_vec());
+        Self {
+            translation: translation.into(),
+            rotation: Quat::from_array(rotation),
+            scale: scale.into(),
+            weights,
+        }
+    }
+
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+
+    pub fn apply(&mut self, transformation: Transformation) {
+        match transformation {
+            Transformation::Translate(v) => self.translation = v,
+            Transformation::Rotate(q) => self.rotation = q,
+            Transformation::Scale(s) => self.scale = s,
+            Transformation::Weights(w) => self.weights = w,
+        }
+    }
+
+    // t of 0 is self, 1 is other
+    pub fn blend(&self, other: &Self, t: f32, rotation_blend: RotationBlend) -> Self {
+        // q and -q are the same rotation, take whichever is on the short way round
+        let other_rotation = if self.rotation.dot(other.rotation) < 0.0 { -other.rotation } else { other.rotation };
+        let rotation = match rotation_blend {
+            RotationBlend::Nlerp => Quat::from_vec4(Vec4::from(self.rotation).lerp(Vec4::from(other_rotation), t)).normalize(),
+            RotationBlend::Slerp => self.rotation.slerp(other_rotation, t).normalize(),
+        };
+        let weights = if self.weights.len() == other.weights.len() {
+            self.weights.iter().zip(&other.weights).map(|(a, b)| a + (b - a) * t).collect()
+        } else if t < 0.5 {
+            self.weights.clone()
+        } else {
+            other.weights.clone()
+        };
+        Self {
+            translation: self.translation.lerp(other.translation, t),
+            rotation,
+            scale: self.scale.lerp(other.scale, t),
+            weights,
+        }
+    }
+}
+
+// local transforms of the nodes a pose animates, every other node stays at rest
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Pose {
+    pub nodes: HashMap<usize, NodeTransform>,
+}
+
+impl Pose {
+    // rest is every node's own transform by node index, channels only override what they animate
+    pub fn sample(clip: &AnimationClip, time: f32, rest: &[NodeTransform]) -> Self {
+        let mut nodes = HashMap::new();
+        for (target, transformation) in clip.sample(time) {
+            nodes.entry(target).or_insert_with(|| rest[target].clone()).apply(transformation);
+        }
+        Self { nodes }
+    }
+
+    // weighted blend of any number of poses, weights don't need to add up to 1.
+    // a node one pose doesn't animate counts as its rest transform in that pose
+    pub fn blend(poses: &[(Pose, f32)], rest: &[NodeTransform], rotation_blend: RotationBlend) -> Self {
+        let poses = poses.iter().filter(|(_, weight)| *weight > 0.0).collect::<Vec<_>>();
+        let mut nodes = HashMap::new();
+        for &node in poses.iter().flat_map(|(pose, _)| pose.nodes.keys()) {
+            if nodes.contains_key(&node) {
+                continue;
+            }
+            // folding in one pose at a time, each weighted against the total so far, gives the weighted average
+            let mut blended: Option<NodeTransform> = None;
+            let mut total = 0.0;
+            for (pose, weight) in &poses {
+                let transform = pose.nodes.get(&node).unwrap_or(&rest[node]);
+                total += weight;
+                blended = Some(match blended {
+                    Some(b) => b.blend(transform, weight / total, rotation_blend),
+                    None => transform.clone(),
+                });
+            }
+            nodes.insert(node, blended.unwrap());
+        }
+        Self { nodes }
+    }
+
+    pub fn get(&self, node: usize) -> Option<&NodeTransform> {
+        self.nodes.get(&node)
+    }
+}
+
+// a clip playing in the mixer, with its weight fading from one value to another
+#[derive(Debug, Clone, Copy)]
+pub struct ClipLayer {
+    pub clip: usize,
+    // mixer time the clip started playing at
+    pub started: f32,
+    from_weight: f32,
+    to_weight: f32,
+    fade_start: f32,
+    fade_duration: f32,
+}
+
+impl ClipLayer {
+    pub fn weight(&self, time: f32) -> f32 {
+        if self.fade_duration <= 0.0 {
+            return self.to_weight;
+        }
+        let t = ((time - self.fade_start) / self.fade_duration).clamp(0.0, 1.0);
+        self.from_weight + (self.to_weight - self.from_weight) * t
+    }
+}
+
+// decides which clips play and how much each one counts, the clips themselves stay in the scene
+#[derive(Debug, Clone)]
+pub struct AnimationMixer {
+    pub layers: Vec<ClipLayer>,
+    pub rotation_blend: RotationBlend,
+}
+
+impl AnimationMixer {
+    pub fn new(clip: Option<usize>) -> Self {
+        let mut mixer = Self {
+            layers: Vec::new(),
+            rotation_blend: RotationBlend::Nlerp,
+        };
+        mixer.play(clip, 0.0);
+        mixer
+    }
+
+    // the clip that's playing or fading in, the one faded to last
+    pub fn current(&self) -> Option<usize> {
+        self.layers.iter().rev().find(|l| l.to_weight > 0.0).map(|l| l.clip)
+    }
+
+    // cuts straight to a clip, starting it from the beginning
+    pub fn play(&mut self, clip: Option<usize>, time: f32) {
+        self.layers.clear();
+        self.add_layer(clip, time, 1.0);
+    }
+
+    // plays a clip alongside the others at a fixed weight
+    pub fn add_layer(&mut self, clip: Option<usize>, time: f32, weight: f32) {
+        if let Some(clip) = clip {
+            self.layers.push(ClipLayer { clip, started: time, from_weight: weight, to_weight: weight, fade_start: time, fade_duration: 0.0 });
+        }
+    }
+
+    // starts a clip from the beginning and fades it in over duration while everything else fades out.
+    // fading to None fades out to the rest pose
+    pub fn crossfade(&mut self, clip: Option<usize>, time: f32, duration: f32) {
+        self.layers.retain(|l| l.weight(time) > 0.0);
+        for layer in &mut self.layers {
+            *layer = ClipLayer { from_weight: layer.weight(time), to_weight: 0.0, fade_start: time, fade_duration: duration, ..*layer };
+        }
+        if let Some(clip) = clip {
+            self.layers.push(ClipLayer { clip, started: time, from_weight: 0.0, to_weight: 1.0, fade_start: time, fade_duration: duration });
+        }
+    }
+
+    // every layer with a say in the pose at this time, as (clip, time the clip has played for, weight).
+    // while they add up to less than 1, the rest pose makes up the missing weight
+    pub fn weights(&self, time: f32) -> Vec<(usize, f32, f32)> {
+        self.layers.iter()
+            .map(|l| (l.clip, time - l.started, l.weight(time)))
+            .filter(|&(_, _, weight)| weight > 0.0)
+            .collect()
+    }
+
+    pub fn pose(&self, clips: &[AnimationClip], time: f32, rest: &[NodeTransform]) -> Pose {
+        let mut poses = self.weights(time).into_iter()
+            .map(|(clip, clip_time, weight)| (Pose::sample(&clips[clip], clip_time, rest), weight))
+            .collect::<Vec<_>>();
+        let total = poses.iter().map(|(_, weight)| weight).sum::<f32>();
+        // whatever the clips don't cover is the rest pose's, fading in from it or out to it alike
+        if total < 1.0 {
+            poses.push((Pose::default(), 1.0 - total));
+        }
+        match poses.as_slice() {
+            [] => Pose::default(),
+            [(pose, weight)] if (weight - 1.0).abs() < 1e-6 => pose.clone(),
+            _ => Pose::blend(&poses, rest, self.rotation_blend),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn translate(x: f32) -> Transformation {
+        Transformation::Translate(Vec3::new(x, 0.0, 0.0))
+    }
+
+    fn x(transformation: Option<Transformation>) -> f32 {
+        match transformation {
+            Some(Transformation::Translate(v)) => v.x,
+            other => panic!("expected a translation, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn linear_interpolates_between_keyframes() {
+        let animation = Animation::from_keyframes(0, Interpolation::Linear, &[0.0, 1.0, 2.0], &[translate(0.0), translate(2.0), translate(-2.0)]);
+        assert!((x(animation.get(0.25)) - 0.5).abs() < 1e-5);
+        assert!((x(animation.get(1.0)) - 2.0).abs() < 1e-5);
+        assert!((x(animation.get(1.5)) - 0.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn linear_rotation_slerps() {
+        let q0 = Quat::IDENTITY;
+        let q1 = Quat::from_rotation_y(FRAC_PI_2);
+        let animation = Animation::from_keyframes(0, Interpolation::Linear, &[0.0, 1.0], &[Transformation::Rotate(q0), Transformation::Rotate(q1)]);
+        match animation.get(0.5) {
+            Some(Transformation::Rotate(q)) => assert!(q.angle_between(Quat::from_rotation_y(FRAC_PI_2 / 2.0)) < 1e-4),
+            other => panic!("expected a rotation, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn step_holds_previous_keyframe() {
+        let animation = Animation::from_keyframes(0, Interpolation::Step, &[0.0, 1.0, 2.0], &[translate(0.0), translate(2.0), translate(-2.0)]);
+        assert_eq!(x(animation.get(0.99)), 0.0);
+        assert_eq!(x(animation.get(1.0)), 2.0);
+        assert_eq!(x(animation.get(1.7)), 2.0);
+    }
+
+    #[test]
+    fn holds_outside_keyframe_range() {
+        let animation = Animation::from_keyframes(0, Interpolation::Linear, &[1.0, 2.0], &[translate(3.0), translate(5.0)]);
+        assert_eq!(x(animation.get(0.5)), 3.0);
+        assert_eq!(x(animation.get(4.0)), 5.0);
+    }
+
+    #[test]
+    fn cubic_spline_follows_hermite_curve() {
+        // keys at the ends of y = t^3 with its derivatives as tangents reproduce it exactly
+        let outputs = [translate(0.0), translate(0.0), translate(0.0), translate(3.0), translate(1.0), translate(0.0)];
+        let animation = Animation::from_keyframes(0, Interpolation::CubicSpline, &[0.0, 1.0], &outputs);
+        for t in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            assert!((x(animation.get(t)) - t * t * t).abs() < 1e-5, "t = {}", t);
+        }
+    }
+
+    #[test]
+    fn cubic_spline_scales_tangents_by_keyframe_spacing() {
+        // y = t^3 again, stretched over two seconds so the tangents are per second of the stretched curve
+        let outputs = [translate(0.0), translate(0.0), translate(0.0), translate(1.5), translate(1.0), translate(0.0)];
+        let animation = Animation::from_keyframes(0, Interpolation::CubicSpline, &[0.0, 2.0], &outputs);
+        assert!((x(animation.get(1.0)) - 0.125).abs() < 1e-5);
+    }
+
+    #[test]
+    fn cubic_spline_reads_triples_not_keyframes() {
+        // the value is the middle element of each triple, the tangents mustn't be treated as keys
+        let outputs = [translate(100.0), translate(1.0), translate(0.0), translate(0.0), translate(2.0), translate(-100.0)];
+        let animation = Animation::from_keyframes(0, Interpolation::CubicSpline, &[0.0, 1.0], &outputs);
+        assert_eq!(x(animation.get(0.0)), 1.0);
+        assert_eq!(x(animation.get(1.0)), 2.0);
+        assert!((x(animation.get(0.5)) - 1.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn cubic_spline_rotations_stay_normalized() {
+        let zero = || Transformation::Rotate(Quat::from_xyzw(0.0, 0.0, 0.0, 0.0));
+        let outputs = [zero(), Transformation::Rotate(Quat::IDENTITY), zero(), zero(), Transformation::Rotate(Quat::from_rotation_z(FRAC_PI_2)), zero()];
+        let animation = Animation::from_keyframes(0, Interpolation::CubicSpline, &[0.0, 1.0], &outputs);
+        match animation.get(0.5) {
+            Some(Transformation::Rotate(q)) => {
+                assert!((q.length() - 1.0).abs() < 1e-5);
+                assert!(q.angle_between(Quat::from_rotation_z(FRAC_PI_2 / 2.0)) < 1e-4);
+            },
+            other => panic!("expected a rotation, got {:?}", other),
+        }
+    }
+
+    fn clip(start: f32, end: f32) -> AnimationClip {
+        AnimationClip::new("clip".to_string(), vec![Animation::from_keyframes(0, Interpolation::Linear, &[start, end], &[translate(start), translate(end)])])
+    }
+
+    #[test]
+    fn clip_uses_true_time_range() {
+        let clip = clip(2.0, 5.0);
+        assert_eq!((clip.start, clip.end), (2.0, 5.0));
+        assert_eq!(clip.local_time(0.0), 2.0);
+        assert_eq!(clip.local_time(1.0), 3.0);
+    }
+
+    #[test]
+    fn clip_playback_modes() {
+        let mut clip = clip(1.0, 3.0);
+        assert!((clip.local_time(2.5) - 1.5).abs() < 1e-5);
+        clip.mode = PlaybackMode::Once;
+        assert_eq!(clip.local_time(2.5), 3.0);
+        assert_eq!(clip.local_time(-1.0), 1.0);
+        clip.mode = PlaybackMode::PingPong;
+        assert!((clip.local_time(1.5) - 2.5).abs() < 1e-5);
+        assert!((clip.local_time(2.5) - 2.5).abs() < 1e-5);
+        assert!((clip.local_time(4.5) - 1.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn clip_speed_scales_playback() {
+        let mut clip = clip(0.0, 4.0);
+        clip.speed = 2.0;
+        assert_eq!(clip.local_time(1.0), 2.0);
+        clip.speed = -1.0;
+        assert_eq!(clip.local_time(1.0), 3.0);
+        let (target, transformation) = clip.sample(1.0).next().unwrap();
+        assert_eq!(target, 0);
+        assert!((x(Some(transformation)) - 3.0).abs() < 1e-5);
+        // played once in reverse it starts at the end and stops at the start
+        clip.mode = PlaybackMode::Once;
+        assert_eq!(clip.local_time(0.0), 4.0);
+        assert_eq!(clip.local_time(1.0), 3.0);
+        assert_eq!(clip.local_time(10.0), 0.0);
+    }
+
+    #[test]
+    fn speed_steps_skip_zero() {
+        let mut clip = clip(0.0, 4.0);
+        clip.step_speed(-0.25);
+        clip.step_speed(-0.25);
+        clip.step_speed(-0.25);
+        assert_eq!(clip.speed, 0.25);
+        // straight over 0 to playing backwards
+        clip.step_speed(-0.25);
+        assert_eq!(clip.speed, -0.25);
+        clip.step_speed(0.25);
+        assert_eq!(clip.speed, 0.25);
+        clip.step_speed(0.25);
+        assert_eq!(clip.speed, 0.5);
+    }
+
+    fn at(x: f32) -> NodeTransform {
+        NodeTransform { translation: Vec3::new(x, 0.0, 0.0), ..NodeTransform::IDENTITY }
+    }
+
+    #[test]
+    fn node_transforms_blend_per_component() {
+        let a = NodeTransform { rotation: Quat::IDENTITY, ..at(0.0) };
+        let b = NodeTransform { rotation: -Quat::from_rotation_x(FRAC_PI_2), scale: Vec3::splat(3.0), ..at(2.0) };
+        for rotation_blend in [RotationBlend::Nlerp, RotationBlend::Slerp] {
+            let blended = a.blend(&b, 0.5, rotation_blend);
+            assert!((blended.translation.x - 1.0).abs() < 1e-5);
+            assert!((blended.scale - Vec3::splat(2.0)).length() < 1e-5);
+            // b's rotation is stored negated, blending still takes the short way round
+            assert!(blended.rotation.angle_between(Quat::from_rotation_x(FRAC_PI_2 / 2.0)) < 1e-4);
+        }
+    }
+
+    #[test]
+    fn poses_blend_by_weight_with_rest_for_missing_nodes() {
+        let rest = [at(10.0), at(20.0)];
+        let a = Pose { nodes: [(0, at(0.0))].into_iter().collect() };
+        let b = Pose { nodes: [(0, at(4.0)), (1, at(0.0))].into_iter().collect() };
+        let blended = Pose::blend(&[(a, 1.0), (b, 3.0)], &rest, RotationBlend::Nlerp);
+        assert!((blended.get(0).unwrap().translation.x - 3.0).abs() < 1e-5);
+        assert!((blended.get(1).unwrap().translation.x - 5.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn mixer_crossfades_between_clips() {
+        let clips = [clip(0.0, 10.0), AnimationClip::new("other".to_string(), vec![
+            Animation::from_keyframes(0, Interpolation::Step, &[0.0], &[translate(-4.0)]),
+        ])];
+        let rest = [NodeTransform::IDENTITY];
+        let mut mixer = AnimationMixer::new(Some(0));
+        mixer.crossfade(Some(1), 2.0, 1.0);
+        assert_eq!(mixer.current(), Some(1));
+
+        assert!((mixer.pose(&clips, 2.0, &rest).get(0).unwrap().translation.x - 2.0).abs() < 1e-5);
+        // clip 0 is at 2.5 with half the weight, clip 1 holds -4 with the other half
+        assert!((mixer.pose(&clips, 2.5, &rest).get(0).unwrap().translation.x + 0.75).abs() < 1e-5);
+        assert!((mixer.pose(&clips, 3.0, &rest).get(0).unwrap().translation.x + 4.0).abs() < 1e-5);
+
+        // fading out to nothing blends back towards the rest pose
+        mixer.crossfade(None, 3.0, 2.0);
+        assert_eq!(mixer.current(), None);
+        assert!((mixer.pose(&clips, 4.0, &rest).get(0).unwrap().translation.x + 2.0).abs() < 1e-5);
+        assert_eq!(mixer.pose(&clips, 5.0, &rest), Pose::default());
+    }
+
+    #[test]
+    fn mixer_crossfades_in_from_the_rest_pose() {
+        let clips = [clip(0.0, 10.0)];
+        let rest = [at(4.0)];
+        let mut mixer = AnimationMixer::new(None);
+        mixer.crossfade(Some(0), 2.0, 2.0);
+        assert_eq!(mixer.current(), Some(0));
+
+        assert!((mixer.pose(&clips, 2.0, &rest).get(0).map_or(4.0, |n| n.translation.x) - 4.0).abs() < 1e-5);
+        // the clip is at 1 with half the weight, the rest pose holds 4 with the other half
+        assert!((mixer.pose(&clips, 3.0, &rest).get(0).unwrap().translation.x - 2.5).abs() < 1e-5);
+        assert!((mixer.pose(&clips, 4.0, &rest).get(0).unwrap().translation.x - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn morph_weights_interpolate_per_target() {
+        let weights = |w: &[f32]| Transformation::Weights(w.to_vec());
+        let linear = Animation::from_keyframes(0, Interpolation::Linear, &[0.0, 1.0], &[weights(&[0.0, 1.0]), weights(&[1.0, 0.0])]);
+        assert_eq!(linear.get(0.25), Some(weights(&[0.25, 0.75])));
+
+        let outputs = [weights(&[0.0, 0.0]), weights(&[0.0, 1.0]), weights(&[0.0, 0.0]), weights(&[0.0, 0.0]), weights(&[1.0, 0.0]), weights(&[0.0, 0.0])];
+        let cubic = Animation::from_keyframes(0, Interpolation::CubicSpline, &[0.0, 1.0], &outputs);
+        assert_eq!(cubic.get(0.5), Some(weights(&[0.5, 0.5])));
+
+        let mut rest = NodeTransform { weights: vec![0.3, 0.3], ..NodeTransform::IDENTITY };
+        let other = NodeTransform { weights: vec![0.1, 0.5], ..NodeTransform::IDENTITY };
+        assert!(rest.blend(&other, 0.5, RotationBlend::Nlerp).weights.iter().zip([0.2, 0.4]).all(|(a, b)| (a - b).abs() < 1e-5));
+        rest.apply(weights(&[1.0, 0.0]));
+        assert_eq!(rest.weights, vec![1.0, 0.0]);
+    }
+}
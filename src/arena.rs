@@ -0,0 +1,264 @@
+use wgpu::*;
+use wgpu::util::DeviceExt;
+use bytemuck::{Pod, Zeroable};
+use glam::Mat4;
+use std::num::NonZeroU64;
+use crate::mesh::{Mesh, MorphDelta, VertexKind};
+use crate::pose::ScenePose;
+use crate::culling::CullPass;
+
+// every primitive's vertices, indices and morph deltas, one buffer each for the whole scene, except vertices which get one per VertexKind.
+// indices are already offset to their primitive's first vertex in its kind's buffer, so draws never need a base vertex
+pub struct GeometryArena {
+    vertices: [Buffer; 4],
+    pub indices: Buffer,
+    pub morph_deltas: Buffer,
+}
+
+impl GeometryArena {
+    pub fn vertices(&self, kind: VertexKind) -> &Buffer {
+        &self.vertices[kind.index()]
+    }
+}
+
+// the arena's contents while the scene loads, before any of it is uploaded
+#[derive(Default)]
+pub struct GeometryArenaBuilder {
+    // by VertexKind::index
+    vertices: [Vec<u8>; 4],
+    indices: Vec<u32>,
+    morph_deltas: Vec<MorphDelta>,
+}
+
+// where a primitive's data went in the arena
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArenaRange {
+    pub first_vertex: u32,
+    pub first_index: u32,
+    pub first_delta: u32,
+}
+
+impl GeometryArenaBuilder {
+    // vertices have to be in kind's format. indices are relative to the primitive's own vertices
+    pub fn push<V: Pod>(&mut self, kind: VertexKind, vertices: &[V], indices: &[u32], morph_deltas: &[MorphDelta]) -> ArenaRange {
+        debug_assert_eq!(std::mem::size_of::<V>(), kind.size());
+        let kind_vertices = &mut self.vertices[kind.index()];
+        let range = ArenaRange {
+            first_vertex: (kind_vertices.len() / kind.size()) as u32,
+            first_index: self.indices.len() as u32,
+            first_delta: self.morph_deltas.len() as u32,
+        };
+        kind_vertices.extend_from_slice(bytemuck::cast_slice(vertices));
+        self.indices.extend(indices.iter().map(|i| i + range.first_vertex));
+        self.morph_deltas.extend_from_slice(morph_deltas);
+        range
+    }
+
+    pub fn build(mut self, device: &Device) -> GeometryArena {
+        // empty buffers can't be bound
+        for kind in VertexKind::ALL {
+            if self.vertices[kind.index()].is_empty() {
+                self.vertices[kind.index()].resize(kind.size(), 0);
+            }
+        }
+        if self.indices.is_empty() {
+            self.indices.push(0);
+        }
+        if self.morph_deltas.is_empty() {
+            self.morph_deltas.push(MorphDelta::zeroed());
+        }
+        let create = |label, contents: &[u8], usage| device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some(label),
+            contents,
+            usage,
+        });
+
+        GeometryArena {
+            // gpu skinning reads the skinned ones as storage
+            vertices: VertexKind::ALL.map(|kind| create("arena vertices buffer", &self.vertices[kind.index()], BufferUsages::VERTEX | BufferUsages::STORAGE)),
+            indices: create("arena indices buffer", bytemuck::cast_slice(&self.indices), BufferUsages::INDEX),
+            morph_deltas: create("arena morph deltas buffer", bytemuck::cast_slice(&self.morph_deltas), BufferUsages::STORAGE),
+        }
+    }
+}
+
+// one storage buffer cut into a block per mesh. blocks start on the device's storage offset alignment, so each mesh's
+// bind group can bind just its own, and the whole buffer is still written in one go
+pub struct BlockBuffer {
+    pub buffer: Buffer,
+    // offset and size of every block
+    blocks: Vec<(BufferAddress, BufferAddress)>,
+    size: BufferAddress,
+}
+
+impl BlockBuffer {
+    // element is the size of what the blocks hold, blocks start on a multiple of it too so the whole buffer can be read as one array
+    pub fn new(device: &Device, label: &str, contents: &[Vec<u8>], element: BufferAddress) -> Self {
+        let alignment = (device.limits().min_storage_buffer_offset_alignment as BufferAddress).max(element);
+        let blocks = block_layout(contents.iter().map(|c| c.len() as BufferAddress), alignment);
+        let size = blocks.last().map_or(0, |&(offset, size)| offset + size);
+        let buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some(label),
+            contents: &pack(&blocks, size, contents),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        });
+        Self { buffer, blocks, size }
+    }
+
+    // contents has to have the same sizes the buffer was made with
+    pub fn write(&self, queue: &Queue, contents: &[Vec<u8>]) {
+        queue.write_buffer(&self.buffer, 0, &pack(&self.blocks, self.size, contents));
+    }
+
+    pub fn offset(&self, block: usize) -> BufferAddress {
+        self.blocks[block].0
+    }
+
+    pub fn binding(&self, block: usize) -> BindingResource<'_> {
+        let (offset, size) = self.blocks[block];
+        BindingResource::Buffer(BufferBinding {
+            buffer: &self.buffer,
+            offset,
+            size: NonZeroU64::new(size),
+        })
+    }
+}
+
+// offset and size of each block, every block at least 4 bytes
+fn block_layout(sizes: impl Iterator<Item = BufferAddress>, alignment: BufferAddress) -> Vec<(BufferAddress, BufferAddress)> {
+    let mut offset = 0;
+    sizes.map(|size| {
+        let block = (offset, size.max(4));
+        offset = (offset + block.1).div_ceil(alignment) * alignment;
+        block
+    }).collect()
+}
+
+fn pack(blocks: &[(BufferAddress, BufferAddress)], size: BufferAddress, contents: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = vec![0; size as usize];
+    for (&(offset, _), block) in blocks.iter().zip(contents) {
+        bytes[offset as usize..offset as usize + block.len()].copy_from_slice(block);
+    }
+    bytes
+}
+
+// size of a Model in the shaders, a model and a normal matrix
+pub const MODEL_SIZE: BufferAddress = 2 * std::mem::size_of::<Mat4>() as BufferAddress;
+
+// every mesh's list of the instances a draw should use, as indices into its instances, for each CullPass.
+// CullPass::All lists all of them in order and never changes, the gpu culling pass fills in the rest.
+// a mesh's bind group binds its own list, and draws pick the pass's copy with a dynamic offset
+pub struct VisibleInstances {
+    pub buffer: Buffer,
+    // offset of each mesh's list in a pass's part of the buffer, and its size
+    lists: Vec<(BufferAddress, BufferAddress)>,
+    // bytes per pass
+    pub stride: BufferAddress,
+}
+
+impl VisibleInstances {
+    pub fn new(device: &Device, meshes: &[Mesh], passes: usize) -> Self {
+        let alignment = device.limits().min_storage_buffer_offset_alignment as BufferAddress;
+        let index = std::mem::size_of::<u32>() as BufferAddress;
+        let lists = block_layout(meshes.iter().map(|m| m.instances.len() as BufferAddress * index), alignment);
+        let stride = lists.last().map_or(0, |&(offset, size)| offset + size).div_ceil(alignment) * alignment;
+        let all = meshes.iter().map(|m| bytemuck::cast_slice::<u32, u8>(&(0..m.instance_count()).collect::<Vec<_>>()).to_vec()).collect::<Vec<_>>();
+        let mut contents = pack(&lists, stride, &all);
+        contents.resize((stride as usize * passes).max(4), 0);
+        let buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("visible instances buffer"),
+            contents: &contents,
+            usage: BufferUsages::STORAGE,
+        });
+        Self { buffer, lists, stride }
+    }
+
+    // first index of a mesh's list within each pass's part of the buffer
+    pub fn first_index(&self, mesh: usize) -> u32 {
+        (self.lists[mesh].0 / std::mem::size_of::<u32>() as BufferAddress) as u32
+    }
+
+    // binds a mesh's list in the first pass, draws move it to theirs with offset
+    pub fn binding(&self, mesh: usize) -> BindingResource<'_> {
+        let (offset, size) = self.lists[mesh];
+        BindingResource::Buffer(BufferBinding {
+            buffer: &self.buffer,
+            offset,
+            size: NonZeroU64::new(size),
+        })
+    }
+
+    // the dynamic offset a mesh's bind group needs for a pass's list
+    pub fn offset(&self, pass: CullPass) -> DynamicOffset {
+        (pass.index() as BufferAddress * self.stride) as DynamicOffset
+    }
+}
+
+// everything about the meshes that changes from frame to frame, a few buffers for the whole scene with a block per mesh
+pub struct MeshBuffers {
+    // model and normal matrices of every instance
+    pub transforms: BlockBuffer,
+    pub joint_matrices: BlockBuffer,
+    // the morph weights header then the weights
+    pub morph_weights: BlockBuffer,
+    pub visible: VisibleInstances,
+}
+
+impl MeshBuffers {
+    // lights is how many lights the scene has, each gets a CullPass
+    pub fn new(device: &Device, meshes: &[Mesh], pose: &ScenePose, lights: usize) -> Self {
+        let contents = MeshContents::new(meshes, pose);
+        Self {
+            transforms: BlockBuffer::new(device, "mesh transforms buffer", &contents.transforms, MODEL_SIZE),
+            joint_matrices: BlockBuffer::new(device, "joint matrices buffer", &contents.joint_matrices, std::mem::size_of::<Mat4>() as BufferAddress),
+            morph_weights: BlockBuffer::new(device, "morph weights buffer", &contents.morph_weights, std::mem::size_of::<u32>() as BufferAddress),
+            visible: VisibleInstances::new(device, meshes, CullPass::count(lights)),
+        }
+    }
+
+    // one write per buffer, however many meshes there are
+    pub fn write(&self, queue: &Queue, meshes: &[Mesh], pose: &ScenePose) {
+        let contents = MeshContents::new(meshes, pose);
+        self.transforms.write(queue, &contents.transforms);
+        self.joint_matrices.write(queue, &contents.joint_matrices);
+        self.morph_weights.write(queue, &contents.morph_weights);
+    }
+}
+
+struct MeshContents {
+    transforms: Vec<Vec<u8>>,
+    joint_matrices: Vec<Vec<u8>>,
+    morph_weights: Vec<Vec<u8>>,
+}
+
+impl MeshContents {
+    // also brings every mesh's instance matrices and world bounds up to date with the pose
+    fn new(meshes: &[Mesh], pose: &ScenePose) -> Self {
+        let mut contents = Self {
+            transforms: Vec::with_capacity(meshes.len()),
+            joint_matrices: Vec::with_capacity(meshes.len()),
+            morph_weights: Vec::with_capacity(meshes.len()),
+        };
+        for mesh in meshes {
+            let joint_matrices = pose.mesh_joints(mesh);
+            let weights = &pose.weights[mesh.node()];
+            contents.transforms.push(bytemuck::cast_slice::<Mat4, u8>(&mesh.instance_matrices(&pose.world)).to_vec());
+            mesh.update_bounds(&joint_matrices, weights);
+            contents.joint_matrices.push(bytemuck::cast_slice::<Mat4, u8>(&joint_matrices).to_vec());
+            contents.morph_weights.push(bytemuck::cast_slice::<u32, u8>(&mesh.morph_weights(weights)).to_vec());
+        }
+        contents
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_start_aligned() {
+        assert_eq!(block_layout([128, 256, 0, 64].into_iter(), 256), vec![(0, 128), (256, 256), (512, 4), (768, 64)]);
+        let blocks = block_layout([2, 3].into_iter(), 4);
+        assert_eq!(pack(&blocks, 8, &[vec![1, 2], vec![3, 4, 5]]), vec![1, 2, 0, 0, 3, 4, 5, 0]);
+    }
+}
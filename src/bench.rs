@@ -0,0 +1,240 @@
+use crate::context::Context;
+use crate::scene::LoadOptions;
+use crate::strategy::BindGroupStrategy;
+use crate::profiler::FrameTimings;
+use crate::culling::CullStats;
+use anyhow::Result;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+// bump whenever the layout of the json report changes
+pub const REPORT_VERSION: u32 = 3;
+
+// animation time step between frames, so every run sees the same frames
+const FRAME_STEP: f32 = 1.0 / 60.0;
+
+pub struct BenchConfig {
+    pub warmup: usize,
+    pub frames: usize,
+    pub replicates: usize,
+    pub width: u32,
+    pub height: u32,
+    pub strategies: Vec<BindGroupStrategy>,
+    pub scenes: Vec<PathBuf>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct Stats {
+    pub mean: f64,
+    pub median: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub cv: f64,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[u64]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+
+        let n = sorted.len() as f64;
+        let mean = sorted.iter().map(|&s| s as f64).sum::<f64>() / n;
+        let variance = sorted.iter().map(|&s| (s as f64 - mean).powi(2)).sum::<f64>() / n;
+
+        Some(Self {
+            mean,
+            median: percentile(&sorted, 0.5),
+            p95: percentile(&sorted, 0.95),
+            p99: percentile(&sorted, 0.99),
+            cv: if mean > 0.0 { variance.sqrt() / mean } else { 0.0 },
+        })
+    }
+}
+
+// linearly interpolated percentile of already sorted samples
+fn percentile(sorted: &[u64], p: f64) -> f64 {
+    let rank = p * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let t = rank - lower as f64;
+    sorted[lower] as f64 * (1.0 - t) + sorted[upper] as f64 * t
+}
+
+#[derive(Serialize, Debug)]
+pub struct PassStats {
+    pub name: String,
+    pub stats: Option<Stats>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BenchResult {
+    pub strategy: BindGroupStrategy,
+    pub scene: String,
+    pub replicate: usize,
+    pub stats: Option<Stats>,
+    pub frame_times_ns: Vec<u64>,
+    // whether pass timings are gpu timestamps or only cpu recording time
+    pub gpu_pass_timings: bool,
+    pub passes: Vec<PassStats>,
+    // meshes drawn and culled in the last measured frame
+    pub culling: CullStats,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BenchReport {
+    pub version: u32,
+    pub warmup: usize,
+    pub frames: usize,
+    pub width: u32,
+    pub height: u32,
+    pub results: Vec<BenchResult>,
+}
+
+impl BenchReport {
+    pub fn write_json(&self, path: impl AsRef<Path>) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    // the `---name---` followed by one frame time per line format that graph.py reads
+    pub fn write_legacy(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let multiple_scenes = self.results.iter().any(|r| r.scene != self.results[0].scene);
+        for result in &self.results {
+            if multiple_scenes {
+                writeln!(file, "---{}-{}---", result.strategy, result.scene)?;
+            } else {
+                writeln!(file, "---{}---", result.strategy)?;
+            }
+            for time in &result.frame_times_ns {
+                writeln!(file, "{}", time)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub async fn run(config: &BenchConfig) -> Result<BenchReport> {
+    let mut results = Vec::new();
+
+    // replicates go round robin over every strategy and scene rather than back to back,
+    // so slow drift (thermals, background load) doesn't all land on one strategy
+    for replicate in 0..config.replicates {
+        for scene in &config.scenes {
+            for &strategy in &config.strategies {
+                let scene_name = scene.file_stem().map_or_else(|| scene.display().to_string(), |s| s.to_string_lossy().into_owned());
+                eprintln!("Benchmarking {} on {} ({}/{})", strategy, scene_name, replicate + 1, config.replicates);
+
+                let mut state = Context::new_headless_with(config.width, config.height, scene, &LoadOptions::default(), strategy).await?;
+                state.profiler.enabled = true;
+
+                let (frame_times_ns, pass_timings) = time_frames(&state, config.warmup, config.frames)?;
+                results.push(BenchResult {
+                    strategy,
+                    scene: scene_name,
+                    replicate,
+                    stats: Stats::from_samples(&frame_times_ns),
+                    frame_times_ns,
+                    gpu_pass_timings: state.profiler.uses_timestamps(),
+                    passes: pass_stats(&pass_timings),
+                    culling: state.cull_stats(),
+                });
+            }
+        }
+    }
+
+    Ok(BenchReport {
+        version: REPORT_VERSION,
+        warmup: config.warmup,
+        frames: config.frames,
+        width: config.width,
+        height: config.height,
+        results,
+    })
+}
+
+// renders warmup frames untimed, then times each measured frame through to the gpu finishing it.
+// pass timings are read back after the clock stops so they don't count towards the frame time
+fn time_frames(state: &Context, warmup: usize, frames: usize) -> Result<(Vec<u64>, Vec<FrameTimings>)> {
+    for i in 0..warmup {
+        state.render(i as f32 * FRAME_STEP)?;
+        state.wait_idle();
+    }
+    let mut frame_times = Vec::with_capacity(frames);
+    let mut pass_timings = Vec::with_capacity(frames);
+    for i in warmup..warmup + frames {
+        let start = Instant::now();
+        state.render(i as f32 * FRAME_STEP)?;
+        state.wait_idle();
+        frame_times.push(start.elapsed().as_nanos() as u64);
+        pass_timings.extend(state.frame_timings());
+    }
+    Ok((frame_times, pass_timings))
+}
+
+// stats per pass name, in the order the passes first show up
+fn pass_stats(timings: &[FrameTimings]) -> Vec<PassStats> {
+    let mut samples: Vec<(String, Vec<u64>)> = Vec::new();
+    for pass in timings.iter().flat_map(|t| &t.passes) {
+        match samples.iter_mut().find(|(name, _)| *name == pass.name) {
+            Some((_, durations)) => durations.push(pass.duration_ns),
+            None => samples.push((pass.name.clone(), vec![pass.duration_ns])),
+        }
+    }
+    samples.into_iter().map(|(name, durations)| PassStats { name, stats: Stats::from_samples(&durations) }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn stats_of_known_samples() {
+        // given out of order, which from_samples sorts
+        let stats = Stats::from_samples(&[4, 1, 3, 2]).unwrap();
+        assert!(close(stats.mean, 2.5));
+        // an even count has its median halfway between the middle two
+        assert!(close(stats.median, 2.5));
+        // rank 0.95 * 3 = 2.85, so 85% of the way from 3 to 4
+        assert!(close(stats.p95, 3.85));
+        assert!(close(stats.p99, 3.97));
+        // population standard deviation sqrt(1.25) over the mean
+        assert!(close(stats.cv, 1.25_f64.sqrt() / 2.5));
+
+        let odd = Stats::from_samples(&[5, 1, 9]).unwrap();
+        assert!(close(odd.median, 5.0));
+    }
+
+    #[test]
+    fn single_and_missing_samples() {
+        let stats = Stats::from_samples(&[7]).unwrap();
+        assert!(close(stats.mean, 7.0) && close(stats.median, 7.0) && close(stats.p95, 7.0) && close(stats.p99, 7.0));
+        assert!(close(stats.cv, 0.0));
+        assert!(close(Stats::from_samples(&[0, 0]).unwrap().cv, 0.0));
+        assert!(Stats::from_samples(&[]).is_none());
+    }
+
+    #[test]
+    fn percentiles_interpolate_between_ranks() {
+        let hundred_and_one = (0..=100).collect::<Vec<_>>();
+        // ranks land exactly on samples
+        assert!(close(percentile(&hundred_and_one, 0.95), 95.0));
+        assert!(close(percentile(&hundred_and_one, 0.99), 99.0));
+        let eleven = (0..=10).map(|s| s * 10).collect::<Vec<_>>();
+        // rank 9.5, halfway between 90 and 100, not rounded to either
+        assert!(close(percentile(&eleven, 0.95), 95.0));
+        assert!(close(percentile(&eleven, 0.99), 99.0));
+        assert!(close(percentile(&eleven, 0.0), 0.0));
+        assert!(close(percentile(&eleven, 1.0), 100.0));
+    }
+}
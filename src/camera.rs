@@ -0,0 +1,260 @@
+use glam::{Vec3, Vec4, Mat3, Mat4};
+use wgpu::*;
+use wgpu::util::DeviceExt;
+use gltf::camera::Projection as GltfProjection;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Projection {
+    Perspective { yfov: f32, aspect: f32, near: f32, far: f32 },
+    // xmag and ymag are half the width and height of the view volume, as in glTF
+    Orthographic { xmag: f32, ymag: f32, near: f32, far: f32 },
+}
+
+impl Projection {
+    pub fn matrix(&self) -> Mat4 {
+        match *self {
+            Projection::Perspective { yfov, aspect, near, far } => Mat4::perspective_rh(yfov, aspect, near, far),
+            Projection::Orthographic { xmag, ymag, near, far } => Mat4::orthographic_rh(-xmag, xmag, -ymag, ymag, near, far),
+        }
+    }
+
+    pub fn set_aspect(&mut self, new_aspect: f32) {
+        match self {
+            Projection::Perspective { aspect, .. } => *aspect = new_aspect,
+            Projection::Orthographic { xmag, ymag, .. } => *xmag = *ymag * new_aspect,
+        }
+    }
+
+    // the other kind of projection, with what's distance in front of the camera about the same size on screen
+    pub fn toggled(self, distance: f32) -> Self {
+        match self {
+            Projection::Perspective { yfov, aspect, near, far } => {
+                let ymag = distance * (yfov / 2.0).tan();
+                Projection::Orthographic { xmag: ymag * aspect, ymag, near, far }
+            },
+            Projection::Orthographic { xmag, ymag, near, far } => Projection::Perspective {
+                yfov: 2.0 * (ymag / distance).atan(),
+                aspect: xmag / ymag,
+                near,
+                far,
+            },
+        }
+    }
+}
+
+pub struct Camera {
+    pub eye: Vec3,
+    pub target: Vec3,
+    pub up: Vec3,
+    pub projection: Projection,
+    pub mat_buffer: Buffer,
+    pub pos_buffer: Buffer,
+    pub bind_group: BindGroup,
+    pub layout: BindGroupLayout,
+}
+
+impl Camera {
+    pub fn new(device: &Device, eye: Vec3, target: Vec3, up: Vec3, projection: Projection) -> Self {
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }
+            ],
+            label: Some("camera layout"),
+        });
+
+        let view_mat = Mat4::look_at_rh(eye, target, up);
+        let proj_mat = projection.matrix();
+        let mat_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("camera mat buffer"),
+            contents: bytemuck::cast_slice(&[proj_mat, view_mat]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let pos_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("camera pos buffer"),
+            contents: bytemuck::cast_slice(&position_uniform(proj_mat, view_mat, eye, target, &projection)),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout: &layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: mat_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: pos_buffer.as_entire_binding(),
+                }
+            ],
+            label: Some("camera bind group"),
+        });
+        
+        Self {
+            mat_buffer,
+            pos_buffer,
+            eye,
+            target,
+            up,
+            projection,
+            bind_group,
+            layout,
+        }
+    }
+
+    pub fn from_gltf(device: &Device, proj: GltfProjection, mat: Mat4) -> Self {
+        let eye = mat.transform_point3(Vec3::new(0.0, 0.0, 0.0));
+        let target = mat.transform_point3(Vec3::new(0.0, 0.0, -1.0));
+        let up = mat.transform_vector3(Vec3::new(0.0, 1.0, 0.0));
+
+        let projection = match proj {
+            GltfProjection::Perspective(p) => Projection::Perspective {
+                yfov: p.yfov(),
+                aspect: p.aspect_ratio().unwrap_or(1.333),
+                near: p.znear(),
+                far: p.zfar().unwrap_or(50.0),
+            },
+            GltfProjection::Orthographic(o) => Projection::Orthographic {
+                xmag: o.xmag(),
+                ymag: o.ymag(),
+                near: o.znear(),
+                far: o.zfar(),
+            },
+        };
+
+        Self::new(device, eye, target, up, projection)
+    }
+
+    pub fn update(&mut self, queue: &Queue, mat: Mat3) {
+        self.eye = mat * self.eye;
+        self.target = mat * self.target;
+        self.up = (mat * self.up).normalize();
+        let u_proj = self.get_proj_mat();
+        let u_view = self.get_view_mat();
+        let slice = position_uniform(u_proj, u_view, self.eye, self.target, &self.projection);
+        queue.write_buffer(&self.mat_buffer, 0, bytemuck::cast_slice(&[u_proj, u_view]));
+        queue.write_buffer(&self.pos_buffer, 0, bytemuck::cast_slice(&slice));
+    }
+
+    pub fn get_view_mat(&self) -> Mat4 {
+        Mat4::look_at_rh(self.eye, self.target, self.up)
+    }
+
+    pub fn get_proj_mat(&self) -> Mat4 {
+        self.projection.matrix()
+    }
+
+    pub fn set_aspect(&mut self, new_aspect: f32) {
+        self.projection.set_aspect(new_aspect);
+    }
+
+    // swaps between perspective and orthographic, keeping what's at the target about the same size on screen
+    pub fn toggle_projection(&mut self) {
+        self.projection = self.projection.toggled((self.target - self.eye).length());
+    }
+}
+
+// Camera_Pos in the shaders: the inverse matrices, the eye, and the way it looks with w 1 when every view ray does
+fn position_uniform(proj: Mat4, view: Mat4, eye: Vec3, target: Vec3, projection: &Projection) -> [Vec4; 10] {
+    let inv_proj = proj.inverse();
+    let inv_view = view.inverse();
+    let orthographic = matches!(projection, Projection::Orthographic { .. });
+    [
+        inv_proj.col(0),
+        inv_proj.col(1),
+        inv_proj.col(2),
+        inv_proj.col(3),
+        inv_view.col(0),
+        inv_view.col(1),
+        inv_view.col(2),
+        inv_view.col(3),
+        eye.extend(1.0),
+        (target - eye).normalize().extend(orthographic as u32 as f32),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec2;
+
+    const PERSPECTIVE: Projection = Projection::Perspective { yfov: 1.0, aspect: 2.0, near: 0.1, far: 50.0 };
+    const ORTHOGRAPHIC: Projection = Projection::Orthographic { xmag: 4.0, ymag: 2.0, near: 0.1, far: 50.0 };
+
+    #[test]
+    fn projections_map_their_volume_to_clip_space() {
+        // depth goes from 0 at near to 1 at far, looking down -z
+        let near = PERSPECTIVE.matrix().project_point3(Vec3::new(0.0, 0.0, -0.1));
+        let far = PERSPECTIVE.matrix().project_point3(Vec3::new(0.0, 0.0, -50.0));
+        assert!(near.z.abs() < 1e-5 && (far.z - 1.0).abs() < 1e-5);
+        // the top of the view at distance 1 is tan(yfov / 2) up, and the side twice as far out
+        let corner = PERSPECTIVE.matrix().project_point3(Vec3::new(2.0 * 0.5_f32.tan(), 0.5_f32.tan(), -1.0));
+        assert!(corner.truncate().abs_diff_eq(Vec2::ONE, 1e-5));
+
+        // orthographic corners are at xmag and ymag whatever the depth
+        for z in [-0.1, -1.0, -50.0] {
+            let corner = ORTHOGRAPHIC.matrix().project_point3(Vec3::new(4.0, -2.0, z));
+            assert!(corner.truncate().abs_diff_eq(Vec2::new(1.0, -1.0), 1e-5));
+        }
+    }
+
+    #[test]
+    fn aspect_changes_the_width() {
+        let mut perspective = PERSPECTIVE;
+        perspective.set_aspect(1.5);
+        assert_eq!(perspective, Projection::Perspective { yfov: 1.0, aspect: 1.5, near: 0.1, far: 50.0 });
+        let mut orthographic = ORTHOGRAPHIC;
+        orthographic.set_aspect(1.5);
+        assert_eq!(orthographic, Projection::Orthographic { xmag: 3.0, ymag: 2.0, near: 0.1, far: 50.0 });
+    }
+
+    #[test]
+    fn toggling_keeps_the_target_the_same_size() {
+        let distance = 7.0;
+        let orthographic = PERSPECTIVE.toggled(distance);
+        // a point at the top right of the view at the target's distance stays there
+        let corner = |projection: Projection| projection.matrix().project_point3(Vec3::new(2.0 * 0.5_f32.tan() * distance, 0.5_f32.tan() * distance, -distance));
+        assert!(corner(PERSPECTIVE).truncate().abs_diff_eq(Vec2::ONE, 1e-5));
+        assert!(corner(orthographic).truncate().abs_diff_eq(Vec2::ONE, 1e-5));
+        assert!(matches!(orthographic, Projection::Orthographic { near, far, .. } if near == 0.1 && far == 50.0));
+
+        // and toggling back gives the perspective it started from
+        match (orthographic.toggled(distance), PERSPECTIVE) {
+            (Projection::Perspective { yfov, aspect, .. }, Projection::Perspective { yfov: start_yfov, aspect: start_aspect, .. }) => {
+                assert!((yfov - start_yfov).abs() < 1e-5 && (aspect - start_aspect).abs() < 1e-5);
+            },
+            _ => panic!("Toggling twice should give a perspective projection"),
+        }
+    }
+
+    #[test]
+    fn orthographic_cameras_say_their_rays_are_parallel() {
+        let eye = Vec3::new(0.0, 0.0, 5.0);
+        let view = Mat4::look_at_rh(eye, Vec3::ZERO, Vec3::Y);
+        let uniform = position_uniform(ORTHOGRAPHIC.matrix(), view, eye, Vec3::ZERO, &ORTHOGRAPHIC);
+        assert_eq!(uniform[8], Vec4::new(0.0, 0.0, 5.0, 1.0));
+        assert_eq!(uniform[9], Vec4::new(0.0, 0.0, -1.0, 1.0));
+        assert_eq!(position_uniform(PERSPECTIVE.matrix(), view, eye, Vec3::ZERO, &PERSPECTIVE)[9].w, 0.0);
+    }
+}
+
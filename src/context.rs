@@ -0,0 +1,1500 @@
+use wgpu::*;
+use anyhow::{Result, anyhow};
+use winit::window::Window;
+use crate::scene::{Scene, LoadOptions};
+use crate::mesh::{Mesh, VertexKind};
+use crate::light::Light;
+use crate::blur::Blur;
+use crate::texture::{Texture, MipTexture};
+use crate::strategy::BindGroupStrategy;
+use crate::profiler::{Profiler, FrameTimings};
+use crate::culling::{CullPass, CullStats, Frustum, PassCulling};
+use crate::gpu_culling::GpuCulling;
+use crate::skinning::GpuSkinning;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::path::Path;
+use core::num::NonZeroU32;
+use glam::Mat3;
+use gltf::material::AlphaMode;
+
+// where the post pass ends up: either the window's surface, or a texture
+// that can be copied into a mapped buffer and read back on the cpu
+enum RenderTarget {
+    Surface {
+        surface: Surface,
+        config: SurfaceConfiguration,
+    },
+    Offscreen {
+        texture: wgpu::Texture,
+        buffer: Buffer,
+        padded_bytes_per_row: u32,
+    },
+}
+
+impl RenderTarget {
+    fn offscreen(device: &Device, format: TextureFormat, width: u32, height: u32) -> Self {
+        let texture = device.create_texture(&TextureDescriptor {
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            label: Some("offscreen texture"),
+        });
+
+        // rows in a texture to buffer copy have to be padded out to COPY_BYTES_PER_ROW_ALIGNMENT
+        let bytes_per_row = width * 4;
+        let padded_bytes_per_row = bytes_per_row.div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT) * COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("offscreen readback buffer"),
+            size: (padded_bytes_per_row * height) as BufferAddress,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self::Offscreen { texture, buffer, padded_bytes_per_row }
+    }
+}
+
+// the bloom chain needs 5 mips, so nothing sized to the screen can be smaller than this
+const MIN_SCREEN_TEXTURE_SIZE: u32 = 16;
+
+fn screen_texture_size(width: u32, height: u32) -> (u32, u32) {
+    (width.max(MIN_SCREEN_TEXTURE_SIZE), height.max(MIN_SCREEN_TEXTURE_SIZE))
+}
+
+// where a pass reads a mesh's vertices from: the arena's buffer for the mesh's format, or what gpu skinning wrote
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VertexSource {
+    Arena(VertexKind),
+    Skinned,
+}
+
+impl VertexSource {
+    // the format of the vertices, which picks the pipelines
+    fn kind(self) -> VertexKind {
+        match self {
+            VertexSource::Arena(kind) => kind,
+            VertexSource::Skinned => VertexKind::Static,
+        }
+    }
+}
+
+pub struct Context {
+    device: Device,
+    target: RenderTarget,
+    // mesh pipelines come one per VertexKind, by VertexKind::index
+    geometry_pipelines: [RenderPipeline; 4],
+    masked_geometry_pipelines: [RenderPipeline; 4],
+    shading_pipeline: RenderPipeline,
+    post_pipeline: RenderPipeline,
+    shadow_pipelines: [RenderPipeline; 4],
+    masked_shadow_pipelines: [RenderPipeline; 4],
+    forward_coverage_pipelines: [RenderPipeline; 4],
+    forward_ambient_pipelines: [RenderPipeline; 4],
+    forward_pipelines: [RenderPipeline; 4],
+    ambient_pipeline: RenderPipeline,
+    emissive_pipeline: RenderPipeline,
+    blur_pipeline: RenderPipeline,
+    blit_pipeline: RenderPipeline,
+    depth_texture: Texture,
+    material_texture: Texture,
+    diffuse_texture: Texture,
+    normal_texture: Texture,
+    emissive_texture: Texture,
+    blurred_texture_vertical: MipTexture,
+    blurred_texture_horizontal: MipTexture,
+    blurred_texture_all: MipTexture,
+    blurs: [Blur; 4],
+    object_layout: BindGroupLayout,
+    light_layout: BindGroupLayout,
+    texture_layout: BindGroupLayout,
+    depth_layout: BindGroupLayout,
+    format: TextureFormat,
+    minimized: bool,
+    strategy: BindGroupStrategy,
+    pub profiler: Profiler,
+    cull_stats: RefCell<CullStats>,
+    // None where the adapter can't cull on the gpu, the passes cull on the cpu instead
+    gpu_culling: Option<GpuCulling>,
+    // None where the adapter has no compute shaders, skinned meshes are skinned in every pass's vertex shader instead
+    gpu_skinning: Option<GpuSkinning>,
+    pub width: u32,
+    pub height: u32,
+    pub scene: Scene,
+    pub queue: Queue,
+}
+
+impl Context {
+    pub async fn new(window: &Window, file_path: impl AsRef<Path>, options: &LoadOptions, strategy: BindGroupStrategy) -> Result<Self> {
+
+        let width = window.inner_size().width;
+        let height = window.inner_size().height;
+
+        // create device, queue
+        let instance = Instance::new(Backends::PRIMARY);
+        let surface = unsafe { instance.create_surface(window) };
+        let adapter = instance.request_adapter(
+            &RequestAdapterOptionsBase {
+                power_preference: PowerPreference::default(),
+                compatible_surface: Some(&surface),
+                force_fallback_adapter: false,
+            }
+        ).await.ok_or(anyhow!("Couldn't get adapter"))?;
+        let (device, queue) = Self::request_device(&adapter).await?;
+        let format = surface.get_preferred_format(&adapter).ok_or(anyhow!("Incompatible surface!"))?;
+
+        let config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width,
+            height,
+            present_mode: PresentMode::Immediate,
+        };
+        surface.configure(&device, &config);
+
+        Self::from_device(&adapter, device, queue, RenderTarget::Surface { surface, config }, format, width, height, file_path, options, strategy)
+    }
+
+    // renders into an offscreen texture instead of a window, for machines without a display.
+    // software adapters (lavapipe, the wgpu fallback adapter) are fine here, so if there's no
+    // real gpu we ask for the fallback one
+    pub async fn new_headless(width: u32, height: u32, scene_path: impl AsRef<Path>) -> Result<Self> {
+        Self::new_headless_with(width, height, scene_path, &LoadOptions::default(), BindGroupStrategy::default()).await
+    }
+
+    // new_headless with the scene's load options and a bind group strategy other than the default
+    pub async fn new_headless_with(width: u32, height: u32, scene_path: impl AsRef<Path>, options: &LoadOptions, strategy: BindGroupStrategy) -> Result<Self> {
+        // textures can't be empty, so this is caught here rather than by wgpu's validation
+        if width == 0 || height == 0 {
+            return Err(anyhow!("Can't render headless at {}x{}, both sides need at least a pixel", width, height));
+        }
+        let instance = Instance::new(Backends::PRIMARY);
+        let mut adapter_options = RequestAdapterOptionsBase {
+            power_preference: PowerPreference::default(),
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        };
+        let adapter = match instance.request_adapter(&adapter_options).await {
+            Some(adapter) => adapter,
+            None => {
+                adapter_options.force_fallback_adapter = true;
+                instance.request_adapter(&adapter_options).await.ok_or(anyhow!("Couldn't get adapter"))?
+            },
+        };
+        let (device, queue) = Self::request_device(&adapter).await?;
+        let format = TextureFormat::Rgba8UnormSrgb;
+        let target = RenderTarget::offscreen(&device, format, width, height);
+
+        Self::from_device(&adapter, device, queue, target, format, width, height, scene_path, options, strategy)
+    }
+
+    async fn request_device(adapter: &Adapter) -> Result<(Device, Queue)> {
+        Ok(adapter.request_device(
+            &DeviceDescriptor{
+                // used by the profiler and gpu culling when they're there
+                features: adapter.features() & (Features::TIMESTAMP_QUERY | Features::MULTI_DRAW_INDIRECT),
+                limits: wgpu::Limits {
+                    max_bind_groups: 8, // set max number of bind groups to 8 as it defaults to 4
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            None,
+            ).await?)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn from_device(adapter: &Adapter, device: Device, queue: Queue, target: RenderTarget, format: TextureFormat, width: u32, height: u32, file_path: impl AsRef<Path>, options: &LoadOptions, strategy: BindGroupStrategy) -> Result<Self> {
+
+        let profiler = Profiler::new(&device, &queue);
+
+        // create required layouts
+        let (object_layout, light_layout, texture_layout, depth_layout, depth_layout_comparison) = {
+            // material textures (base color, metallic roughness, normal, occlusion, emissive) then their samplers
+            let material_texture_entries = (3..8).map(|binding| BindGroupLayoutEntry {
+                binding,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: true },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            });
+            let material_sampler_entries = (8..13).map(|binding| BindGroupLayoutEntry {
+                binding,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler {
+                    filtering: true,
+                    comparison: false,
+                },
+                count: None,
+            });
+            let object_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                entries: &[
+                    // model and normal matrices of every instance
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::VERTEX,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: ShaderStages::VERTEX,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }
+                ].into_iter().chain(material_texture_entries).chain(material_sampler_entries).chain([13, 14].map(|binding| {
+                    // morph target deltas, then the weights
+                    BindGroupLayoutEntry {
+                        binding,
+                        visibility: ShaderStages::VERTEX,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }
+                })).chain([
+                    // the instances to draw, the dynamic offset picks which pass's list
+                    BindGroupLayoutEntry {
+                        binding: 15,
+                        visibility: ShaderStages::VERTEX,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: true,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }
+                ]).collect::<Vec<_>>(),
+                label: Some("object layout"),
+            });
+
+            let light_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }
+                ],
+                label: Some("light layout"),
+            });
+
+            let texture_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: true },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler {
+                            filtering: true,
+                            comparison: false,
+                        },
+                        count: None,
+                    }
+                ],
+                label: Some("texture layout"),
+            });
+
+            let depth_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Depth,
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler {
+                            filtering: true,
+                            comparison: false,
+                        },
+                        count: None,
+                    }
+                ],
+                label: Some("depth layout"),
+            });
+
+            let depth_layout_comparison = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Depth,
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler {
+                            filtering: true,
+                            comparison: true,
+                        },
+                        count: None,
+                    },
+                ],
+                label: Some("depth layout comparison"),
+            });
+            (object_layout, light_layout, texture_layout, depth_layout, depth_layout_comparison)
+        };
+
+        // load mesh
+        let mut scene = Scene::from_gltf(&device, &queue, &object_layout, &light_layout, &depth_layout_comparison, file_path, options)?;
+
+        let blend_component = BlendComponent {
+            operation: BlendOperation::Add,
+            src_factor: BlendFactor::One,
+            dst_factor: BlendFactor::One,
+        };
+
+        // create required textures
+        let (texture_width, texture_height) = screen_texture_size(width, height);
+        let diffuse_texture = Texture::create_window_texture(&device, &texture_layout, TextureFormat::Rgb10a2Unorm, None, texture_width, texture_height);
+        let material_texture = Texture::create_window_texture(&device, &texture_layout, TextureFormat::Rgba16Float, None, texture_width, texture_height);
+        let normal_texture = Texture::create_window_texture(&device, &texture_layout, TextureFormat::Rgba16Float, None, texture_width, texture_height);
+        let emissive_texture = Texture::create_window_texture(&device, &texture_layout, TextureFormat::Rgba16Float, None, texture_width, texture_height);
+        let depth_texture = Texture::create_window_texture(&device, &depth_layout, TextureFormat::Depth32Float, None, texture_width, texture_height);
+        let gpu_skinning = GpuSkinning::supported(adapter).then(|| GpuSkinning::new(&device, &mut scene));
+        let gpu_culling = GpuCulling::supported(adapter).then(|| GpuCulling::new(&device, &scene, gpu_skinning.as_ref(), &draw_order(&scene), &depth_texture.view, texture_width, texture_height));
+
+        // set up geometry pipeline
+        let (geometry_pipelines, masked_geometry_pipelines) = {
+            let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                bind_group_layouts: &[
+                    &scene.camera.layout,
+                    &object_layout,
+                ],
+                push_constant_ranges: &[],
+                label: Some("geometry pipeline layout"),
+            });
+
+            let shader = {
+                let shader_str = include_str!("./shaders/geometry.wgsl");
+                device.create_shader_module(&ShaderModuleDescriptor {
+                    label: Some("geometry module"),
+                    source: ShaderSource::Wgsl(Cow::Borrowed(shader_str)),
+                })
+            };
+
+            let create_pipeline = |kind: VertexKind, entry_point| device.create_render_pipeline(&RenderPipelineDescriptor {
+                vertex: VertexState {
+                    module: &shader,
+                    entry_point: kind.entry_point(),
+                    buffers: &[
+                        kind.desc(),
+                    ],
+                },
+                fragment: Some(FragmentState {
+                    module: &shader,
+                    entry_point,
+                    targets: &[
+                        diffuse_texture.format.into(),
+                        material_texture.format.into(),
+                        normal_texture.format.into(),
+                        emissive_texture.format.into(),
+                    ],
+                }),
+                layout: Some(&layout),
+                primitive: PrimitiveState::default(),
+                multisample: MultisampleState::default(),
+                depth_stencil: Some(DepthStencilState {
+                    format: depth_texture.format,
+                    depth_write_enabled: true,
+                    depth_compare: CompareFunction::Less,
+                    stencil: StencilState::default(),
+                    bias: DepthBiasState::default(),
+                }),
+                label: Some("geometry pipeline"),
+            });
+            (VertexKind::ALL.map(|kind| create_pipeline(kind, "fs_main")), VertexKind::ALL.map(|kind| create_pipeline(kind, "fs_masked")))
+        };
+
+        // set up shadow pipeline
+        let (shadow_pipelines, masked_shadow_pipelines) = {
+            let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                bind_group_layouts: &[
+                    &light_layout,
+                    &object_layout,
+                ],
+                push_constant_ranges: &[],
+                label: Some("shadow pipeline layout"),
+            });
+
+            let shader = {
+                let shader_str = include_str!("./shaders/shadow.wgsl");
+                device.create_shader_module(&ShaderModuleDescriptor {
+                    label: Some("shadow module"),
+                    source: ShaderSource::Wgsl(Cow::Borrowed(shader_str)),
+                })
+            };
+
+            // only alpha masked meshes need a fragment stage
+            let create_pipeline = |kind: VertexKind, fragment| device.create_render_pipeline(&RenderPipelineDescriptor {
+                vertex: VertexState {
+                    module: &shader,
+                    entry_point: kind.entry_point(),
+                    buffers: &[
+                        kind.desc(),
+                    ],
+                },
+                fragment,
+                layout: Some(&layout),
+                primitive: PrimitiveState::default(),
+                multisample: MultisampleState::default(),
+                depth_stencil: Some(DepthStencilState {
+                    format: TextureFormat::Depth32Float,
+                    depth_write_enabled: true,
+                    depth_compare: CompareFunction::LessEqual,
+                    stencil: StencilState::default(),
+                    bias: DepthBiasState {
+                        constant: 2,
+                        slope_scale: 4.0,
+                        clamp: 0.0,
+                    },
+                }),
+                label: Some("shadow pipeline"),
+            });
+            let masked_fragment = FragmentState {
+                module: &shader,
+                entry_point: "fs_masked",
+                targets: &[],
+            };
+            (VertexKind::ALL.map(|kind| create_pipeline(kind, None)), VertexKind::ALL.map(|kind| create_pipeline(kind, Some(masked_fragment.clone()))))
+        };
+
+        // pre-post blurred screen texture
+        let num_mips = 5;
+        let blurred_texture_vertical = MipTexture::new(&device, &texture_layout, texture_width, texture_height, num_mips);
+        let blurred_texture_horizontal = MipTexture::new(&device, &texture_layout, texture_width, texture_height, num_mips);
+        let blurred_texture_all = MipTexture::new(&device, &texture_layout, texture_width, texture_height, num_mips);
+
+        // set up ambient pipeline
+        let ambient_pipeline = {
+            let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                bind_group_layouts: &[
+                    &light_layout,
+                    &scene.camera.layout,
+                    &texture_layout,
+                    &texture_layout,
+                    &depth_layout,
+                    &scene.sky.layout,
+                    &texture_layout,
+                ],
+                push_constant_ranges: &[],
+                label: Some("ambient pipeline"),
+            });
+
+            let shader = {
+                let shader_str = include_str!("./shaders/ambient.wgsl");
+                device.create_shader_module(&ShaderModuleDescriptor {
+                    label: Some("ambient module"),
+                    source: ShaderSource::Wgsl(Cow::Borrowed(shader_str)),
+                })
+            };
+
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                vertex: VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                fragment: Some(FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[
+                        ColorTargetState {
+                            format: blurred_texture_all.format,
+                            blend: Some(BlendState {
+                                color: blend_component,
+                                alpha: blend_component,
+                            }),
+                            write_mask: ColorWrites::default(),
+                        },
+                    ],
+                }),
+                layout: Some(&layout),
+                primitive: PrimitiveState::default(),
+                multisample: MultisampleState::default(),
+                depth_stencil: None,
+                label: Some("ambient pipeline"),
+            })
+        };
+
+        // set up light pipeline
+        let shading_pipeline = {
+            let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                bind_group_layouts: &[
+                    &light_layout,
+                    &scene.camera.layout,
+                    &texture_layout,
+                    &texture_layout,
+                    &depth_layout,
+                    &texture_layout,
+                    &depth_layout_comparison,
+                ],
+                push_constant_ranges: &[],
+                label: Some("shading pipeline layout"),
+            });
+
+            let shader = {
+                let shader_str = include_str!("./shaders/shading.wgsl");
+                device.create_shader_module(&ShaderModuleDescriptor {
+                    label: Some("shading module"),
+                    source: ShaderSource::Wgsl(Cow::Borrowed(shader_str)),
+                })
+            };
+
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                vertex: VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                fragment: Some(FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[
+                        ColorTargetState {
+                            format: blurred_texture_all.format,
+                            blend: Some(BlendState {
+                                color: blend_component,
+                                alpha: blend_component,
+                            }),
+                            write_mask: ColorWrites::default(),
+                        },
+                    ],
+                }),
+                layout: Some(&layout),
+                primitive: PrimitiveState::default(),
+                multisample: MultisampleState::default(),
+                depth_stencil: None,
+                label: Some("shading pipeline"),
+            })
+        };
+
+        // set up forward pipelines for alpha blended meshes, one per fragment entry point
+        let (forward_coverage_pipelines, forward_ambient_pipelines, forward_pipelines) = {
+            let shader = {
+                let shader_str = include_str!("./shaders/forward.wgsl");
+                device.create_shader_module(&ShaderModuleDescriptor {
+                    label: Some("forward module"),
+                    source: ShaderSource::Wgsl(Cow::Borrowed(shader_str)),
+                })
+            };
+
+            // coverage scales what's already there by (1 - alpha), lights then add alpha times their contribution
+            let keep_alpha = BlendComponent {
+                operation: BlendOperation::Add,
+                src_factor: BlendFactor::Zero,
+                dst_factor: BlendFactor::One,
+            };
+            let coverage_blend = BlendState {
+                color: BlendComponent {
+                    operation: BlendOperation::Add,
+                    src_factor: BlendFactor::One,
+                    dst_factor: BlendFactor::OneMinusSrcAlpha,
+                },
+                alpha: keep_alpha,
+            };
+            let light_blend = BlendState {
+                color: BlendComponent {
+                    operation: BlendOperation::Add,
+                    src_factor: BlendFactor::SrcAlpha,
+                    dst_factor: BlendFactor::One,
+                },
+                alpha: keep_alpha,
+            };
+
+            let create_pipelines = |entry_point, blend, bind_group_layouts: &[&BindGroupLayout]| {
+                let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                    bind_group_layouts,
+                    push_constant_ranges: &[],
+                    label: Some("forward pipeline layout"),
+                });
+
+                VertexKind::ALL.map(|kind| device.create_render_pipeline(&RenderPipelineDescriptor {
+                    vertex: VertexState {
+                        module: &shader,
+                        entry_point: kind.entry_point(),
+                        buffers: &[
+                            kind.desc(),
+                        ],
+                    },
+                    fragment: Some(FragmentState {
+                        module: &shader,
+                        entry_point,
+                        targets: &[
+                            ColorTargetState {
+                                format: blurred_texture_all.format,
+                                blend: Some(blend),
+                                write_mask: ColorWrites::default(),
+                            },
+                        ],
+                    }),
+                    layout: Some(&layout),
+                    primitive: PrimitiveState::default(),
+                    multisample: MultisampleState::default(),
+                    // tested against the opaque depth from the geometry pass, but never written
+                    depth_stencil: Some(DepthStencilState {
+                        format: depth_texture.format,
+                        depth_write_enabled: false,
+                        depth_compare: CompareFunction::Less,
+                        stencil: StencilState::default(),
+                        bias: DepthBiasState::default(),
+                    }),
+                    label: Some("forward pipeline"),
+                }))
+            };
+
+            (
+                create_pipelines("fs_coverage", coverage_blend, &[&scene.camera.layout, &object_layout]),
+                create_pipelines("fs_ambient", light_blend, &[&scene.camera.layout, &object_layout, &light_layout]),
+                create_pipelines("fs_main", light_blend, &[&scene.camera.layout, &object_layout, &light_layout, &depth_layout_comparison]),
+            )
+        };
+
+
+        let blurs = [
+            Blur::new(3.1, 9, &device),
+            Blur::new(6.225, 18, &device), 
+            Blur::new(10.125, 30, &device), 
+            Blur::new(16.4375, 48, &device), 
+        ];
+
+        // set up blur pipeline
+        let blur_pipeline = {
+            let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                bind_group_layouts: &[
+                    &texture_layout,
+                    &blurs[0].layout,
+                ],
+                push_constant_ranges: &[],
+                label: Some("blur pipeline layout"),
+            });
+
+            let shader = {
+                let shader_str = include_str!("./shaders/blur.wgsl");
+                device.create_shader_module(&ShaderModuleDescriptor {
+                    label: Some("blur module"),
+                    source: ShaderSource::Wgsl(Cow::Borrowed(shader_str)),
+                })
+            };
+
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                vertex: VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                fragment: Some(FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[
+                        blurred_texture_vertical.format.into(),
+                    ],
+                }),
+                layout: Some(&layout),
+                primitive: PrimitiveState::default(),
+                multisample: MultisampleState::default(),
+                depth_stencil: None,
+                label: Some("blur pipeline"),
+            })
+        };
+
+        // set up postprocess pipeline
+        let post_pipeline = {
+            let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                bind_group_layouts: &[
+                    &texture_layout,
+                    &texture_layout,
+                    &texture_layout,
+                    &texture_layout,
+                    &texture_layout,
+                ],
+                push_constant_ranges: &[],
+                label: Some("post pipeline layout"),
+            });
+
+            let shader = {
+                let shader_str = include_str!("./shaders/post.wgsl");
+                device.create_shader_module(&ShaderModuleDescriptor {
+                    label: Some("post module"),
+                    source: ShaderSource::Wgsl(Cow::Borrowed(shader_str)),
+                })
+            };
+
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                vertex: VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                fragment: Some(FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[
+                        format.into(),
+                    ],
+                }),
+                layout: Some(&layout),
+                primitive: PrimitiveState::default(),
+                multisample: MultisampleState::default(),
+                depth_stencil: None,
+                label: Some("post pipeline"),
+            })
+        };
+
+        // set up blit pipeline
+        let blit_pipeline = {
+            let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                bind_group_layouts: &[
+                    &texture_layout,
+                ],
+                push_constant_ranges: &[],
+                label: Some("blit pipeline layout"),
+            });
+
+            let shader = {
+                let shader_str = include_str!("./shaders/blit.wgsl");
+                device.create_shader_module(&ShaderModuleDescriptor {
+                    label: Some("post module"),
+                    source: ShaderSource::Wgsl(Cow::Borrowed(shader_str)),
+                })
+            };
+
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("blit pipeline"),
+                layout: Some(&layout),
+                vertex: VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                fragment: Some(FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[blurred_texture_vertical.format.into()],
+                }),
+                primitive: PrimitiveState::default(),
+                multisample: MultisampleState::default(),
+                depth_stencil: None,
+            })
+        };
+
+        // adds the emission g-buffer onto the shaded image, it's a blit with additive blending
+        let emissive_pipeline = {
+            let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                bind_group_layouts: &[
+                    &texture_layout,
+                ],
+                push_constant_ranges: &[],
+                label: Some("emissive pipeline layout"),
+            });
+
+            let shader = {
+                let shader_str = include_str!("./shaders/blit.wgsl");
+                device.create_shader_module(&ShaderModuleDescriptor {
+                    label: Some("emissive module"),
+                    source: ShaderSource::Wgsl(Cow::Borrowed(shader_str)),
+                })
+            };
+
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("emissive pipeline"),
+                layout: Some(&layout),
+                vertex: VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                fragment: Some(FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[
+                        ColorTargetState {
+                            format: blurred_texture_vertical.format,
+                            blend: Some(BlendState {
+                                color: blend_component,
+                                alpha: blend_component,
+                            }),
+                            write_mask: ColorWrites::default(),
+                        },
+                    ],
+                }),
+                primitive: PrimitiveState::default(),
+                multisample: MultisampleState::default(),
+                depth_stencil: None,
+            })
+        };
+
+        Ok(Self {
+            device,
+            target,
+            queue,
+            geometry_pipelines,
+            masked_geometry_pipelines,
+            shading_pipeline,
+            shadow_pipelines,
+            masked_shadow_pipelines,
+            forward_coverage_pipelines,
+            forward_ambient_pipelines,
+            forward_pipelines,
+            blur_pipeline,
+            post_pipeline,
+            ambient_pipeline,
+            emissive_pipeline,
+            material_texture,
+            diffuse_texture,
+            normal_texture,
+            emissive_texture,
+            blurred_texture_vertical,
+            blurred_texture_horizontal,
+            blurred_texture_all,
+            blit_pipeline,
+            blurs,
+            object_layout,
+            light_layout,
+            texture_layout,
+            depth_layout,
+            format,
+            minimized: false,
+            strategy,
+            profiler,
+            cull_stats: RefCell::new(CullStats::default()),
+            gpu_culling,
+            gpu_skinning,
+            width,
+            height,
+            scene,
+            depth_texture,
+        })
+    }
+
+    // rebuilds everything sized to the window: the surface (or offscreen target),
+    // the g-buffer, the bloom mip chains and their bind groups, and the camera's aspect ratio
+    pub fn resize(&mut self, width: u32, height: u32) {
+        // minimized windows report a zero size, there's nothing to render into until they come back
+        self.minimized = width == 0 || height == 0;
+        if self.minimized || (width == self.width && height == self.height) {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+
+        match &mut self.target {
+            RenderTarget::Surface { surface, config } => {
+                config.width = width;
+                config.height = height;
+                surface.configure(&self.device, config);
+            },
+            RenderTarget::Offscreen { .. } => {
+                self.target = RenderTarget::offscreen(&self.device, self.format, width, height);
+            },
+        }
+
+        let (texture_width, texture_height) = screen_texture_size(width, height);
+        self.diffuse_texture = Texture::create_window_texture(&self.device, &self.texture_layout, self.diffuse_texture.format, None, texture_width, texture_height);
+        self.material_texture = Texture::create_window_texture(&self.device, &self.texture_layout, self.material_texture.format, None, texture_width, texture_height);
+        self.normal_texture = Texture::create_window_texture(&self.device, &self.texture_layout, self.normal_texture.format, None, texture_width, texture_height);
+        self.emissive_texture = Texture::create_window_texture(&self.device, &self.texture_layout, self.emissive_texture.format, None, texture_width, texture_height);
+        self.depth_texture = Texture::create_window_texture(&self.device, &self.depth_layout, self.depth_texture.format, None, texture_width, texture_height);
+        if let Some(gpu_culling) = &mut self.gpu_culling {
+            gpu_culling.resize(&self.device, &self.scene, &self.depth_texture.view, texture_width, texture_height);
+        }
+
+        let num_mips = self.blurred_texture_vertical.mip_level_count;
+        self.blurred_texture_vertical = MipTexture::new(&self.device, &self.texture_layout, texture_width, texture_height, num_mips);
+        self.blurred_texture_horizontal = MipTexture::new(&self.device, &self.texture_layout, texture_width, texture_height, num_mips);
+        self.blurred_texture_all = MipTexture::new(&self.device, &self.texture_layout, texture_width, texture_height, num_mips);
+
+        self.scene.camera.set_aspect(width as f32 / height as f32);
+        self.scene.camera.update(&self.queue, Mat3::IDENTITY);
+    }
+
+    pub fn render(&self, elapsed_time: f32) -> Result<()> {
+        if self.minimized {
+            return Ok(());
+        }
+        self.scene.animate(elapsed_time, &self.queue);
+        let (frame, window_view) = match &self.target {
+            RenderTarget::Surface { surface, config } => {
+                let frame = match surface.get_current_texture() {
+                    Ok(frame) => frame,
+                    // the surface needs to be set up again (e.g. it changed size under us), so skip this frame
+                    Err(SurfaceError::Lost | SurfaceError::Outdated) => {
+                        surface.configure(&self.device, config);
+                        return Ok(());
+                    },
+                    Err(e) => return Err(e.into()),
+                };
+                let view = frame.texture.create_view(&TextureViewDescriptor::default());
+                (Some(frame), view)
+            },
+            RenderTarget::Offscreen { texture, .. } => (None, texture.create_view(&TextureViewDescriptor::default())),
+        };
+
+        // made once for every pass that draws meshes, under the strategy that makes them every frame
+        let mesh_bind_groups = self.mesh_bind_groups();
+        let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor::default());
+        self.profiler.begin_frame();
+        let mut cull_stats = CullStats::default();
+        let camera_frustum = Frustum::from_matrix(self.scene.camera.get_proj_mat() * self.scene.camera.get_view_mat());
+
+        // skinning pass, so every pass after it can draw skinned meshes as static ones
+        if let Some(gpu_skinning) = &self.gpu_skinning {
+            self.profiler.begin_pass(&mut encoder, "skinning");
+            gpu_skinning.record(&mut encoder);
+            self.profiler.end_pass(&mut encoder);
+        }
+
+        // culling pass, reads the depth the last frame left behind so it has to go before the geometry pass clears it
+        if let Some(gpu_culling) = &self.gpu_culling {
+            gpu_culling.prepare(&self.queue, &self.scene);
+            self.profiler.begin_pass(&mut encoder, "culling");
+            gpu_culling.record(&mut encoder);
+            self.profiler.end_pass(&mut encoder);
+        }
+
+        // geometry pass
+        self.profiler.begin_pass(&mut encoder, "geometry");
+        {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("geometry pass"),
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: &self.depth_texture.view,
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Clear(1.0),
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
+                color_attachments: &[
+                    RenderPassColorAttachment {
+                        resolve_target: None,
+                        view: &self.diffuse_texture.view,
+                        ops: Operations {
+                            load: LoadOp::Clear(Color::BLACK),
+                            store: true,
+                        }
+                    },
+                    RenderPassColorAttachment {
+                        resolve_target: None,
+                        view: &self.material_texture.view,
+                        ops: Operations {
+                            load: LoadOp::Clear(Color::BLACK),
+                            store: true,
+                        }
+                    },
+                    RenderPassColorAttachment {
+                        resolve_target: None,
+                        view: &self.normal_texture.view,
+                        ops: Operations {
+                            load: LoadOp::Clear(Color::BLACK),
+                            store: true,
+                        }
+                    },
+                    RenderPassColorAttachment {
+                        resolve_target: None,
+                        view: &self.emissive_texture.view,
+                        ops: Operations {
+                            load: LoadOp::Clear(Color::BLACK),
+                            store: true,
+                        }
+                    },
+                ],
+            });
+
+            render_pass.set_bind_group(0, &self.scene.camera.bind_group, &[]);
+            render_pass.set_index_buffer(self.scene.arena.indices.slice(..), IndexFormat::Uint32);
+            // blended meshes are left out, the forward pass draws those
+            for source in self.vertex_sources() {
+                render_pass.set_vertex_buffer(0, self.vertex_buffer(source).slice(..));
+                for (pipelines, alpha_mode) in [(&self.geometry_pipelines, AlphaMode::Opaque), (&self.masked_geometry_pipelines, AlphaMode::Mask)] {
+                    render_pass.set_pipeline(&pipelines[source.kind().index()]);
+                    let meshes = self.scene.meshes.iter().enumerate()
+                        .filter(|(_, mesh)| self.vertex_source(mesh) == source && self.scene.material(mesh).alpha_mode == alpha_mode)
+                        .filter(|(_, mesh)| self.gpu_culling.is_some() || cull_stats.geometry.count(mesh.is_visible(&camera_frustum)))
+                        .map(|(i, _)| i)
+                        .collect::<Vec<_>>();
+                    self.draw_culled(&mut render_pass, &meshes, &mesh_bind_groups, CullPass::Camera);
+                }
+            }
+        }
+        self.profiler.end_pass(&mut encoder);
+
+        // shadow passes
+        for (i, light) in self.scene.lights.iter().enumerate() {
+            match light {
+                Light::Punctual { texture, bind_group, .. } => {
+                    self.profiler.begin_pass(&mut encoder, format!("shadow {}", i));
+                    {
+                        let light_bind_group = light.frame_bind_group(self.strategy, &self.device, &self.light_layout);
+                        let light_frustum = Frustum::from_matrix(light.view_proj().expect("Punctual lights have a shadow map"));
+                        let mut shadow_culling = PassCulling::default();
+                        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                            label: Some("shadow pass"),
+                            depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                                view: &texture.view,
+                                depth_ops: Some(Operations {
+                                    load: LoadOp::Clear(1.0),
+                                    store: true,
+                                }),
+                                stencil_ops: None,
+                            }),
+                            color_attachments: &[],
+                        });
+
+                        render_pass.set_bind_group(0, light_bind_group.as_ref().unwrap_or(bind_group), &[]);
+                        render_pass.set_index_buffer(self.scene.arena.indices.slice(..), IndexFormat::Uint32);
+                        // blended meshes don't cast shadows
+                        for source in self.vertex_sources() {
+                            render_pass.set_vertex_buffer(0, self.vertex_buffer(source).slice(..));
+                            for (pipelines, alpha_mode) in [(&self.shadow_pipelines, AlphaMode::Opaque), (&self.masked_shadow_pipelines, AlphaMode::Mask)] {
+                                render_pass.set_pipeline(&pipelines[source.kind().index()]);
+                                let meshes = self.scene.meshes.iter().enumerate()
+                                    .filter(|(_, mesh)| self.vertex_source(mesh) == source && self.scene.material(mesh).alpha_mode == alpha_mode)
+                                    .filter(|(_, mesh)| self.gpu_culling.is_some() || shadow_culling.count(mesh.is_visible(&light_frustum)))
+                                    .map(|(m, _)| m)
+                                    .collect::<Vec<_>>();
+                                self.draw_culled(&mut render_pass, &meshes, &mesh_bind_groups, CullPass::Shadow(i));
+                            }
+                        }
+                        cull_stats.shadows.push(shadow_culling);
+                    }
+                    self.profiler.end_pass(&mut encoder);
+                },
+                Light::Ambient { .. } => {},
+            }
+        }
+
+        // shading pass
+        self.profiler.begin_pass(&mut encoder, "shading");
+        {
+            let light_bind_groups = self.scene.lights.iter()
+                .map(|light| light.frame_bind_group(self.strategy, &self.device, &self.light_layout))
+                .collect::<Vec<_>>();
+            let sky_bind_group = self.scene.sky.frame_bind_group(self.strategy, &self.device);
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("shading pass"),
+                depth_stencil_attachment: None,
+                color_attachments: &[
+                    RenderPassColorAttachment {
+                        resolve_target: None,
+                        view: &self.blurred_texture_vertical.views[0],
+                        ops: Operations {
+                            load: LoadOp::Clear(Color::BLACK),
+                            store: true,
+                        }
+                    }
+                ],
+            });
+
+            render_pass.set_pipeline(&self.shading_pipeline);
+            render_pass.set_bind_group(1, &self.scene.camera.bind_group, &[]);
+            render_pass.set_bind_group(2, &self.diffuse_texture.bind_group, &[]);
+            render_pass.set_bind_group(3, &self.normal_texture.bind_group, &[]);
+            render_pass.set_bind_group(4, &self.depth_texture.bind_group, &[]);
+            render_pass.set_bind_group(5, &self.material_texture.bind_group, &[]);
+            for (light, frame_bind_group) in self.scene.lights.iter().zip(&light_bind_groups) {
+                match light {
+                    Light::Punctual { texture, bind_group, .. } => {
+                        render_pass.set_bind_group(6, &texture.bind_group, &[]);
+                        render_pass.set_bind_group(0, frame_bind_group.as_ref().unwrap_or(bind_group), &[]);
+                        render_pass.draw(0..3, 0..1);
+                    },
+                    Light::Ambient { .. } => {},
+                }
+            }
+            render_pass.set_pipeline(&self.ambient_pipeline);
+            render_pass.set_bind_group(5, sky_bind_group.as_ref().unwrap_or(&self.scene.sky.bind_group), &[]);
+            render_pass.set_bind_group(6, &self.material_texture.bind_group, &[]);
+            for (light, frame_bind_group) in self.scene.lights.iter().zip(&light_bind_groups) {
+                match light {
+                    Light::Ambient { bind_group, .. } => {
+                        render_pass.set_bind_group(0, frame_bind_group.as_ref().unwrap_or(bind_group), &[]);
+                        render_pass.draw(0..3, 0..1);
+                    },
+                    Light::Punctual { .. } => {},
+                }
+            }
+            // emission goes in once, on top of all the lights, so it makes it into the bloom
+            render_pass.set_pipeline(&self.emissive_pipeline);
+            render_pass.set_bind_group(0, &self.emissive_texture.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        self.profiler.end_pass(&mut encoder);
+
+        // forward pass for blended meshes, back to front over the shaded image
+        let blended = self.blended_meshes();
+        if !blended.is_empty() {
+            self.profiler.begin_pass(&mut encoder, "forward");
+            {
+                let light_bind_groups = self.scene.lights.iter()
+                    .map(|light| light.frame_bind_group(self.strategy, &self.device, &self.light_layout))
+                    .collect::<Vec<_>>();
+                let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: Some("forward pass"),
+                    depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                        view: &self.depth_texture.view,
+                        depth_ops: Some(Operations {
+                            load: LoadOp::Load,
+                            store: true,
+                        }),
+                        stencil_ops: None,
+                    }),
+                    color_attachments: &[
+                        RenderPassColorAttachment {
+                            resolve_target: None,
+                            view: &self.blurred_texture_vertical.views[0],
+                            ops: Operations {
+                                load: LoadOp::Load,
+                                store: true,
+                            }
+                        }
+                    ],
+                });
+
+                render_pass.set_bind_group(0, &self.scene.camera.bind_group, &[]);
+                render_pass.set_index_buffer(self.scene.arena.indices.slice(..), IndexFormat::Uint32);
+                for i in blended {
+                    let mesh = &self.scene.meshes[i];
+                    let source = self.vertex_source(mesh);
+                    let kind = source.kind().index();
+                    let base_vertex = self.base_vertex(i);
+                    render_pass.set_vertex_buffer(0, self.vertex_buffer(source).slice(..));
+                    // blended meshes aren't culled, they're few and sorted already
+                    render_pass.set_bind_group(1, mesh_bind_groups[i].as_ref().or(mesh.bind_group.as_ref()).expect("Unbound mesh!"), &[self.scene.mesh_buffers.visible.offset(CullPass::All)]);
+
+                    render_pass.set_pipeline(&self.forward_coverage_pipelines[kind]);
+                    render_pass.draw_indexed(mesh.geometry.indices(), base_vertex, 0..mesh.instance_count());
+
+                    for (light, frame_bind_group) in self.scene.lights.iter().zip(&light_bind_groups) {
+                        match light {
+                            Light::Ambient { bind_group, .. } => {
+                                render_pass.set_pipeline(&self.forward_ambient_pipelines[kind]);
+                                render_pass.set_bind_group(2, frame_bind_group.as_ref().unwrap_or(bind_group), &[]);
+                            },
+                            Light::Punctual { texture, bind_group, .. } => {
+                                render_pass.set_pipeline(&self.forward_pipelines[kind]);
+                                render_pass.set_bind_group(2, frame_bind_group.as_ref().unwrap_or(bind_group), &[]);
+                                render_pass.set_bind_group(3, &texture.bind_group, &[]);
+                            },
+                        }
+                        render_pass.draw_indexed(mesh.geometry.indices(), base_vertex, 0..mesh.instance_count());
+                    }
+                }
+            }
+            self.profiler.end_pass(&mut encoder);
+        }
+
+        self.profiler.begin_pass(&mut encoder, "bloom mips");
+        self.blurred_texture_vertical.generate_mipmaps(&self.blit_pipeline, &mut encoder);
+        self.profiler.end_pass(&mut encoder);
+
+        // blur pass
+        for i in 1..=4 {
+            self.profiler.begin_pass(&mut encoder, format!("blur {}", i));
+            {
+                let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: Some("blur pass 1"),
+                    depth_stencil_attachment: None,
+                    color_attachments: &[
+                        RenderPassColorAttachment {
+                            resolve_target: None,
+                            view: &self.blurred_texture_horizontal.views[i],
+                            ops: Operations {
+                                load: LoadOp::Clear(Color::BLACK),
+                                store: true,
+                            }
+                        }
+                    ],
+                });
+
+                render_pass.set_pipeline(&self.blur_pipeline);
+                render_pass.set_bind_group(0, &self.blurred_texture_vertical.bind_groups[i], &[]);
+                render_pass.set_bind_group(1, &self.blurs[i - 1].vertical_bind_group, &[]);
+                render_pass.draw(0..3, 0..1);
+            }
+
+            // second blur pass
+            {
+                let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: Some("blur pass 2"),
+                    depth_stencil_attachment: None,
+                    color_attachments: &[
+                        RenderPassColorAttachment {
+                            resolve_target: None,
+                            view: &self.blurred_texture_all.views[i],
+                            ops: Operations {
+                                load: LoadOp::Clear(Color::BLACK),
+                                store: true,
+                            }
+                        }
+                    ],
+                });
+
+                render_pass.set_pipeline(&self.blur_pipeline);
+                render_pass.set_bind_group(0, &self.blurred_texture_horizontal.bind_groups[i], &[]);
+                render_pass.set_bind_group(1, &self.blurs[i - 1].horizontal_bind_group, &[]);
+                render_pass.draw(0..3, 0..1);
+            }
+            self.profiler.end_pass(&mut encoder);
+        }
+
+        // post pass
+        self.profiler.begin_pass(&mut encoder, "post");
+        {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("post pass"),
+                depth_stencil_attachment: None,
+                color_attachments: &[
+                    RenderPassColorAttachment {
+                        resolve_target: None,
+                        view: &window_view,
+                        ops: Operations {
+                            load: LoadOp::Clear(Color::BLACK),
+                            store: true,
+                        }
+                    }
+                ],
+            });
+
+            render_pass.set_pipeline(&self.post_pipeline);
+            render_pass.set_bind_group(0, &self.blurred_texture_vertical.bind_groups[0], &[]);
+            for i in 1..5 {
+                render_pass.set_bind_group(i, &self.blurred_texture_all.bind_groups[i as usize], &[]);
+            }
+            render_pass.draw(0..3, 0..1);
+        }
+        self.profiler.end_pass(&mut encoder);
+
+        self.profiler.resolve(&mut encoder);
+        self.queue.submit(Some(encoder.finish()));
+        if let Some(frame) = frame {
+            frame.present();
+        }
+        self.cull_stats.replace(cull_stats);
+
+        Ok(())
+    }
+
+    // how many meshes the geometry and shadow passes of the last rendered frame drew and culled.
+    // when culling on the gpu this waits on it, like frame_timings
+    pub fn cull_stats(&self) -> CullStats {
+        match &self.gpu_culling {
+            Some(gpu_culling) => gpu_culling.read_stats(&self.device, &self.queue, &self.scene, |m| self.scene.material(&self.scene.meshes[m]).alpha_mode != AlphaMode::Blend),
+            None => self.cull_stats.borrow().clone(),
+        }
+    }
+
+    // per-pass timings of the last rendered frame, if profiling is enabled.
+    // this waits on the gpu, so call it after render rather than in the middle of timing something
+    pub fn frame_timings(&self) -> Option<FrameTimings> {
+        self.profiler.read(&self.device)
+    }
+
+    // blocks until the gpu has finished everything submitted so far
+    pub fn wait_idle(&self) {
+        self.device.poll(Maintain::Wait);
+    }
+
+    // copies the last rendered frame of a headless context back to the cpu as tightly packed rgba8
+    pub async fn read_frame(&self) -> Result<Vec<u8>> {
+        let (texture, buffer, padded_bytes_per_row) = match &self.target {
+            RenderTarget::Offscreen { texture, buffer, padded_bytes_per_row } => (texture, buffer, *padded_bytes_per_row),
+            RenderTarget::Surface { .. } => return Err(anyhow!("Only headless contexts can read frames back")),
+        };
+
+        let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor::default());
+        encoder.copy_texture_to_buffer(
+            ImageCopyTexture {
+                texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            ImageCopyBuffer {
+                buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let mapping = slice.map_async(MapMode::Read);
+        self.device.poll(Maintain::Wait);
+        mapping.await?;
+
+        let bytes_per_row = (self.width * 4) as usize;
+        let pixels = slice.get_mapped_range()
+            .chunks(padded_bytes_per_row as usize)
+            .flat_map(|row| row[..bytes_per_row].iter().copied())
+            .collect();
+        buffer.unmap();
+
+        Ok(pixels)
+    }
+
+    // indices of the alpha blended meshes, sorted back to front by their bounding box centers
+    fn blended_meshes(&self) -> Vec<usize> {
+        let view = self.scene.camera.get_view_mat();
+        let mut blended = self.scene.meshes.iter().enumerate()
+            .filter(|(_, mesh)| self.scene.material(mesh).alpha_mode == AlphaMode::Blend)
+            .map(|(i, mesh)| (i, view.transform_point3(mesh.world_center()).z))
+            .collect::<Vec<_>>();
+        // further away is more negative z in view space
+        blended.sort_by(|a, b| a.1.total_cmp(&b.1));
+        blended.into_iter().map(|(i, _)| i).collect()
+    }
+
+    // draws meshes with the instances a pass's culling left, with indirect draws when they were culled on the gpu,
+    // as few multi draws as their bind groups allow where the device has them.
+    // on the cpu culled meshes were left out of meshes, and every instance of the rest is drawn
+    fn draw_culled<'a>(&'a self, render_pass: &mut RenderPass<'a>, meshes: &[usize], mesh_bind_groups: &'a [Option<BindGroup>], pass: CullPass) {
+        let bind_group = |mesh: usize| mesh_bind_groups[mesh].as_ref().or(self.scene.meshes[mesh].bind_group.as_ref()).expect("Unbound mesh!");
+        match &self.gpu_culling {
+            Some(gpu_culling) => {
+                for batch in gpu_culling.batches(meshes, |a, b| std::ptr::eq(bind_group(a), bind_group(b))) {
+                    render_pass.set_bind_group(1, bind_group(batch[0]), &[self.scene.mesh_buffers.visible.offset(pass)]);
+                    gpu_culling.draw(render_pass, pass, batch);
+                }
+            },
+            None => {
+                for &m in meshes {
+                    let mesh = &self.scene.meshes[m];
+                    render_pass.set_bind_group(1, bind_group(m), &[self.scene.mesh_buffers.visible.offset(CullPass::All)]);
+                    render_pass.draw_indexed(mesh.geometry.indices(), self.base_vertex(m), 0..mesh.instance_count());
+                }
+            },
+        }
+    }
+
+    // the vertex buffers the mesh passes draw from, in the order they draw them
+    fn vertex_sources(&self) -> Vec<VertexSource> {
+        match self.gpu_skinning {
+            Some(_) => vec![VertexSource::Arena(VertexKind::Static), VertexSource::Skinned],
+            None => VertexKind::ALL.map(VertexSource::Arena).to_vec(),
+        }
+    }
+
+    fn vertex_source(&self, mesh: &Mesh) -> VertexSource {
+        if mesh.gpu_skinned {
+            VertexSource::Skinned
+        } else {
+            VertexSource::Arena(mesh.vertex_kind())
+        }
+    }
+
+    fn vertex_buffer(&self, source: VertexSource) -> &Buffer {
+        match (source, &self.gpu_skinning) {
+            (VertexSource::Skinned, Some(gpu_skinning)) => &gpu_skinning.vertices,
+            (VertexSource::Skinned, None) => unreachable!("Only gpu skinning has skinned vertices"),
+            (VertexSource::Arena(kind), _) => self.scene.arena.vertices(kind),
+        }
+    }
+
+    // what a mesh's draws add to its indices
+    fn base_vertex(&self, mesh: usize) -> i32 {
+        self.gpu_skinning.as_ref().map_or(0, |s| s.base_vertex(mesh))
+    }
+
+    // per-frame mesh bind groups, one per mesh, None wherever the strategy reuses the ones made at load
+    fn mesh_bind_groups(&self) -> Vec<Option<BindGroup>> {
+        self.scene.meshes.iter()
+            .enumerate()
+            .map(|(block, mesh)| mesh.frame_bind_group(self.strategy, &self.device, &self.object_layout, self.scene.material(mesh), &self.scene.arena, &self.scene.mesh_buffers, block))
+            .collect()
+    }
+}
+
+// every mesh in the order the geometry and shadow passes draw them: by vertex buffer, the same way vertex_sources orders
+// them, then opaque before masked, with the blended meshes those passes leave out last
+fn draw_order(scene: &Scene) -> Vec<usize> {
+    let source = |mesh: &Mesh| if mesh.gpu_skinned { VertexKind::ALL.len() } else { mesh.vertex_kind().index() };
+    let alpha = |mesh: &Mesh| match scene.material(mesh).alpha_mode {
+        AlphaMode::Opaque => 0,
+        AlphaMode::Mask => 1,
+        AlphaMode::Blend => 2,
+    };
+    let mut order = (0..scene.meshes.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&m| (alpha(&scene.meshes[m]) == 2, source(&scene.meshes[m]), alpha(&scene.meshes[m])));
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn headless_needs_a_size() {
+        // both fail before looking for an adapter, so they don't need a gpu
+        let error = |width, height| block_on(Context::new_headless(width, height, "missing.glb")).err().map(|e| e.to_string());
+        assert!(error(0, 720).unwrap().contains("0x720"));
+        assert!(error(1280, 0).unwrap().contains("1280x0"));
+    }
+}
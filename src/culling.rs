@@ -0,0 +1,125 @@
+use glam::{Mat4, Vec3, Vec4};
+use serde::Serialize;
+
+// the six planes around a view projection's clip volume, normals pointing inwards. depth is 0 to 1 like wgpu's
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    pub fn from_matrix(view_proj: Mat4) -> Self {
+        let [x, y, z, w] = [0, 1, 2, 3].map(|i| view_proj.row(i));
+        Self {
+            planes: [w + x, w - x, w + y, w - y, z, w - z],
+        }
+    }
+
+    // false only when the box is entirely behind one plane, so some boxes just outside a corner still count
+    pub fn intersects(&self, bounds: [Vec3; 2]) -> bool {
+        self.planes.iter().all(|plane| {
+            let normal = plane.truncate();
+            // the corner furthest along the normal
+            let corner = Vec3::select(normal.cmpge(Vec3::ZERO), bounds[1], bounds[0]);
+            normal.dot(corner) + plane.w >= 0.0
+        })
+    }
+}
+
+// the box around a box after a transformation
+pub fn transform_bounds(bounds: [Vec3; 2], matrix: Mat4) -> [Vec3; 2] {
+    let corners = (0..8).map(|i| Vec3::new(
+        if i & 1 == 0 { bounds[0].x } else { bounds[1].x },
+        if i & 2 == 0 { bounds[0].y } else { bounds[1].y },
+        if i & 4 == 0 { bounds[0].z } else { bounds[1].z },
+    ));
+    corners.map(|c| matrix.transform_point3(c)).fold([Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)], |[min, max], c| [min.min(c), max.max(c)])
+}
+
+pub fn union_bounds(a: [Vec3; 2], b: [Vec3; 2]) -> [Vec3; 2] {
+    [a[0].min(b[0]), a[1].max(b[1])]
+}
+
+// which of a mesh's visible instance lists a draw uses. All is every instance, for draws that weren't culled on the gpu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CullPass {
+    All,
+    Camera,
+    // by index into Scene::lights
+    Shadow(usize),
+}
+
+impl CullPass {
+    pub fn index(self) -> usize {
+        match self {
+            CullPass::All => 0,
+            CullPass::Camera => 1,
+            CullPass::Shadow(light) => 2 + light,
+        }
+    }
+
+    // for a scene with this many lights
+    pub fn count(lights: usize) -> usize {
+        2 + lights
+    }
+}
+
+// meshes (draw calls, however many instances they have) a pass drew and left out
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PassCulling {
+    pub drawn: usize,
+    pub culled: usize,
+}
+
+impl PassCulling {
+    pub fn count(&mut self, visible: bool) -> bool {
+        if visible {
+            self.drawn += 1;
+        } else {
+            self.culled += 1;
+        }
+        visible
+    }
+}
+
+// culling in the last rendered frame
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CullStats {
+    pub geometry: PassCulling,
+    // one per punctual light, in light order
+    pub shadows: Vec<PassCulling>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera() -> Frustum {
+        let view = Mat4::look_at_rh(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, Vec3::Y);
+        Frustum::from_matrix(Mat4::perspective_rh(1.0, 1.0, 0.1, 50.0) * view)
+    }
+
+    fn cube(center: Vec3) -> [Vec3; 2] {
+        [center - Vec3::splat(0.5), center + Vec3::splat(0.5)]
+    }
+
+    #[test]
+    fn frustum_keeps_boxes_in_view() {
+        let frustum = camera();
+        assert!(frustum.intersects(cube(Vec3::ZERO)));
+        // straddling the edge of the view still counts
+        assert!(frustum.intersects(cube(Vec3::new(2.4, 0.0, 0.0))));
+        assert!(!frustum.intersects(cube(Vec3::new(10.0, 0.0, 0.0))));
+        assert!(!frustum.intersects(cube(Vec3::new(0.0, 0.0, 10.0))));
+        assert!(!frustum.intersects(cube(Vec3::new(0.0, 0.0, -60.0))));
+    }
+
+    #[test]
+    fn transformed_bounds_cover_every_corner() {
+        let turned = transform_bounds(cube(Vec3::ZERO), Mat4::from_rotation_z(std::f32::consts::FRAC_PI_4));
+        let half = 0.5 * 2.0_f32.sqrt();
+        assert!(turned[0].abs_diff_eq(Vec3::new(-half, -half, -0.5), 1e-5));
+        assert!(turned[1].abs_diff_eq(Vec3::new(half, half, 0.5), 1e-5));
+        assert_eq!(union_bounds(cube(Vec3::ZERO), cube(Vec3::X)), [Vec3::splat(-0.5), Vec3::new(1.5, 0.5, 0.5)]);
+    }
+}
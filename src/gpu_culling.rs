@@ -0,0 +1,568 @@
+use wgpu::*;
+use wgpu::util::DeviceExt;
+use bytemuck::{Pod, Zeroable};
+use futures::executor::block_on;
+use glam::Mat4;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::num::NonZeroU32;
+use crate::arena::MODEL_SIZE;
+use crate::culling::{CullPass, CullStats, PassCulling};
+use crate::light::Light;
+use crate::scene::Scene;
+use crate::skinning::GpuSkinning;
+
+const WORKGROUP_SIZE: u32 = 64;
+const HIZ_WORKGROUP_SIZE: u32 = 8;
+
+// the layouts of the structs of the same names in cull.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct CullMesh {
+    bounds_min: [f32; 4],
+    bounds_max: [f32; 4],
+    first_model: u32,
+    first_visible: u32,
+    draw_slot: u32,
+    padding: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct CullInstance {
+    mesh: u32,
+    instance: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct GpuCullPass {
+    view_proj: Mat4,
+    hiz_view_proj: Mat4,
+    visible_base: u32,
+    occlusion: u32,
+    draw_base: u32,
+    padding: u32,
+}
+
+// what draw_indexed_indirect reads
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct DrawIndexedIndirect {
+    index_count: u32,
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+}
+
+const DRAW_SIZE: BufferAddress = std::mem::size_of::<DrawIndexedIndirect>() as BufferAddress;
+
+// the previous frame's depth as a max pyramid, one storage view per level to build it and one view of them all to test against
+struct HiZ {
+    size: (u32, u32),
+    level_bind_groups: Vec<BindGroup>,
+    cull_bind_group: BindGroup,
+}
+
+// culls every mesh instance against the camera and every light on the gpu before the geometry and shadow passes, and
+// against a hi-z pyramid of the last frame's depth for the camera. it writes the instance counts of the indirect draws
+// those passes make, and the visible instance lists the vertex shaders read through CullPass
+pub struct GpuCulling {
+    copy_pipeline: ComputePipeline,
+    downsample_pipeline: ComputePipeline,
+    cull_pipeline: ComputePipeline,
+    copy_layout: BindGroupLayout,
+    downsample_layout: BindGroupLayout,
+    cull_layout: BindGroupLayout,
+    hiz: HiZ,
+    meshes: Buffer,
+    instances: Buffer,
+    passes: Buffer,
+    pub draws: Buffer,
+    // the draws with every instance count at 0, written over the draws at the start of each frame
+    draw_template: Vec<DrawIndexedIndirect>,
+    readback: Buffer,
+    draws_size: BufferAddress,
+    instance_count: u32,
+    mesh_count: usize,
+    // where each mesh's draw is within a pass's draws
+    draw_slots: Vec<u32>,
+    // whether a run of a pass's draws can go in one multi_draw_indexed_indirect
+    multi_draw: bool,
+    // the passes culled, the camera's and one per light with a shadow map
+    culled_passes: Vec<CullPass>,
+    // the view projection the depth buffer was last rendered with, None while it holds nothing usable
+    previous_view_proj: Cell<Option<Mat4>>,
+}
+
+impl GpuCulling {
+    // wgpu 0.11 has no INDIRECT_FIRST_INSTANCE, and these draws don't need it: first_instance stays 0 and each draw
+    // finds its visible instances through a dynamic offset. what they do need is compute shaders and indirect draws
+    pub fn supported(adapter: &Adapter) -> bool {
+        adapter.get_downlevel_properties().flags.contains(DownlevelFlags::COMPUTE_SHADERS | DownlevelFlags::INDIRECT_EXECUTION)
+    }
+
+    // skinning is where gpu skinned meshes' vertices are, if there is any. draw_order is every mesh in the order the
+    // passes draw them, which is the order each pass's draws are laid out in so that draws made one after another
+    // are next to each other in the buffer
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(device: &Device, scene: &Scene, skinning: Option<&GpuSkinning>, draw_order: &[usize], depth: &TextureView, width: u32, height: u32) -> Self {
+        let storage = |binding, read_only| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let texture = |binding, sample_type| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Texture {
+                sample_type,
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let level_out = BindGroupLayoutEntry {
+            binding: 1,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::StorageTexture {
+                access: StorageTextureAccess::WriteOnly,
+                format: TextureFormat::R32Float,
+                view_dimension: TextureViewDimension::D2,
+            },
+            count: None,
+        };
+        let copy_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[texture(0, TextureSampleType::Depth), level_out],
+            label: Some("hi-z copy layout"),
+        });
+        let downsample_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[texture(0, TextureSampleType::Float { filterable: false }), level_out],
+            label: Some("hi-z downsample layout"),
+        });
+        let cull_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                storage(0, true),
+                storage(1, true),
+                storage(2, true),
+                storage(3, true),
+                storage(4, false),
+                storage(5, false),
+                texture(6, TextureSampleType::Float { filterable: false }),
+            ],
+            label: Some("cull layout"),
+        });
+
+        let pipeline = |label, source: &str, layout: &BindGroupLayout, entry_point| {
+            let module = device.create_shader_module(&ShaderModuleDescriptor {
+                label: Some(label),
+                source: ShaderSource::Wgsl(Cow::Borrowed(source)),
+            });
+            let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: &[layout],
+                push_constant_ranges: &[],
+            });
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(&layout),
+                module: &module,
+                entry_point,
+            })
+        };
+        let hiz_source = include_str!("shaders/hiz.wgsl");
+        let copy_pipeline = pipeline("hi-z copy", hiz_source, &copy_layout, "copy_depth");
+        let downsample_pipeline = pipeline("hi-z downsample", hiz_source, &downsample_layout, "downsample");
+        let cull_pipeline = pipeline("cull", include_str!("shaders/cull.wgsl"), &cull_layout, "main");
+
+        let instances = scene.meshes.iter().enumerate()
+            .flat_map(|(m, mesh)| (0..mesh.instance_count()).map(move |instance| CullInstance { mesh: m as u32, instance }))
+            .collect::<Vec<_>>();
+        let instance_count = instances.len() as u32;
+        let instances = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("cull instances buffer"),
+            // an empty buffer can't be bound
+            contents: if instances.is_empty() { &[0; 8] } else { bytemuck::cast_slice(&instances) },
+            usage: BufferUsages::STORAGE,
+        });
+
+        let mesh_count = scene.meshes.len();
+        let pass_count = CullPass::count(scene.lights.len()) - 1;
+        let draw_template = (0..pass_count).flat_map(|_| draw_order.iter().map(|&m| {
+            let mesh = &scene.meshes[m];
+            DrawIndexedIndirect {
+                index_count: mesh.geometry.length,
+                instance_count: 0,
+                first_index: mesh.geometry.first_index,
+                base_vertex: skinning.map_or(0, |s| s.base_vertex(m)),
+                first_instance: 0,
+            }
+        })).collect::<Vec<_>>();
+        let draws_size = (draw_template.len() as BufferAddress * DRAW_SIZE).max(DRAW_SIZE);
+        let buffer = |label, size, usage| device.create_buffer(&BufferDescriptor {
+            label: Some(label),
+            size,
+            usage,
+            mapped_at_creation: false,
+        });
+        let meshes = buffer("cull meshes buffer", (mesh_count.max(1) * std::mem::size_of::<CullMesh>()) as BufferAddress, BufferUsages::STORAGE | BufferUsages::COPY_DST);
+        // ambient lights have no shadow pass, so there's nothing to cull for them and their draws stay empty
+        let culled_passes = std::iter::once(CullPass::Camera)
+            .chain(scene.lights.iter().enumerate().filter(|(_, light)| light.view_proj().is_some()).map(|(i, _)| CullPass::Shadow(i)))
+            .collect::<Vec<_>>();
+        let passes = buffer("cull passes buffer", (culled_passes.len() * std::mem::size_of::<GpuCullPass>()) as BufferAddress, BufferUsages::STORAGE | BufferUsages::COPY_DST);
+        let draws = buffer("indirect draws buffer", draws_size, BufferUsages::STORAGE | BufferUsages::INDIRECT | BufferUsages::COPY_DST | BufferUsages::COPY_SRC);
+        let readback = buffer("indirect draws readback buffer", draws_size, BufferUsages::MAP_READ | BufferUsages::COPY_DST);
+
+        let hiz = HiZ::new(device, &copy_layout, &downsample_layout, &cull_layout, scene, [&meshes, &instances, &passes, &draws], depth, width, height);
+        Self {
+            copy_pipeline,
+            downsample_pipeline,
+            cull_pipeline,
+            copy_layout,
+            downsample_layout,
+            cull_layout,
+            hiz,
+            meshes,
+            instances,
+            passes,
+            draws,
+            draw_template,
+            readback,
+            draws_size,
+            instance_count,
+            mesh_count,
+            draw_slots: draw_slots(draw_order),
+            multi_draw: device.features().contains(Features::MULTI_DRAW_INDIRECT),
+            culled_passes,
+            previous_view_proj: Cell::new(None),
+        }
+    }
+
+    // the pyramid follows the depth buffer's size, and has nothing to say until it's been drawn into again
+    pub fn resize(&mut self, device: &Device, scene: &Scene, depth: &TextureView, width: u32, height: u32) {
+        self.hiz = HiZ::new(device, &self.copy_layout, &self.downsample_layout, &self.cull_layout, scene, [&self.meshes, &self.instances, &self.passes, &self.draws], depth, width, height);
+        self.previous_view_proj.set(None);
+    }
+
+    // this frame's bounds, view projections and zeroed draws. call after the scene's pose is uploaded
+    pub fn prepare(&self, queue: &Queue, scene: &Scene) {
+        let buffers = &scene.mesh_buffers;
+        let meshes = scene.meshes.iter().enumerate().map(|(m, mesh)| {
+            let [min, max] = *mesh.local_bounds.borrow();
+            CullMesh {
+                bounds_min: min.extend(1.0).to_array(),
+                bounds_max: max.extend(1.0).to_array(),
+                first_model: (buffers.transforms.offset(m) / MODEL_SIZE) as u32,
+                first_visible: buffers.visible.first_index(m),
+                draw_slot: self.draw_slots[m],
+                padding: 0,
+            }
+        }).collect::<Vec<_>>();
+        if !meshes.is_empty() {
+            queue.write_buffer(&self.meshes, 0, bytemuck::cast_slice(&meshes));
+        }
+
+        let camera = scene.camera.get_proj_mat() * scene.camera.get_view_mat();
+        let previous = self.previous_view_proj.replace(Some(camera));
+        let passes = self.culled_passes.iter().map(|&pass| {
+            let (view_proj, hiz_view_proj, occlusion) = match pass {
+                CullPass::Shadow(i) => (scene.lights[i].view_proj().expect("Only lights with shadow maps are culled"), Mat4::IDENTITY, false),
+                _ => (camera, previous.unwrap_or(camera), previous.is_some()),
+            };
+            GpuCullPass {
+                view_proj,
+                hiz_view_proj,
+                visible_base: visible_base(pass, buffers.visible.stride),
+                occlusion: occlusion as u32,
+                draw_base: draw_index(pass, 0, self.mesh_count) as u32,
+                padding: 0,
+            }
+        }).collect::<Vec<_>>();
+        queue.write_buffer(&self.passes, 0, bytemuck::cast_slice(&passes));
+
+        if !self.draw_template.is_empty() {
+            queue.write_buffer(&self.draws, 0, bytemuck::cast_slice(&self.draw_template));
+        }
+    }
+
+    // builds the pyramid from the depth buffer before this frame's geometry pass clears it, then culls
+    pub fn record(&self, encoder: &mut CommandEncoder) {
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor { label: Some("cull pass") });
+        let (width, height) = self.hiz.size;
+        for (level, bind_group) in self.hiz.level_bind_groups.iter().enumerate() {
+            pass.set_pipeline(if level == 0 { &self.copy_pipeline } else { &self.downsample_pipeline });
+            pass.set_bind_group(0, bind_group, &[]);
+            let (level_width, level_height) = ((width >> level).max(1), (height >> level).max(1));
+            pass.dispatch(level_width.div_ceil(HIZ_WORKGROUP_SIZE), level_height.div_ceil(HIZ_WORKGROUP_SIZE), 1);
+        }
+
+        if self.instance_count > 0 {
+            pass.set_pipeline(&self.cull_pipeline);
+            pass.set_bind_group(0, &self.hiz.cull_bind_group, &[]);
+            pass.dispatch(self.instance_count.div_ceil(WORKGROUP_SIZE), self.culled_passes.len() as u32, 1);
+        }
+    }
+
+    // where a mesh's indirect draw for a pass is in draws. CullPass::All is never drawn indirectly
+    pub fn draw_offset(&self, pass: CullPass, mesh: usize) -> BufferAddress {
+        draw_index(pass, self.draw_slots[mesh] as usize, self.mesh_count) as BufferAddress * DRAW_SIZE
+    }
+
+    // splits meshes a pass draws one after another into runs that can each be drawn with one multi draw: their
+    // draws are next to each other, and same(a, b) says a and b are bound the same
+    pub fn batches<'m>(&self, meshes: &'m [usize], same: impl Fn(usize, usize) -> bool) -> Vec<&'m [usize]> {
+        batches(meshes, &self.draw_slots, same)
+    }
+
+    // draws a batch's meshes with the instances the pass left them, in one call where the device can
+    pub fn draw<'a>(&'a self, render_pass: &mut RenderPass<'a>, pass: CullPass, batch: &[usize]) {
+        match batch {
+            [] => {},
+            [first, ..] if self.multi_draw => render_pass.multi_draw_indexed_indirect(&self.draws, self.draw_offset(pass, *first), batch.len() as u32),
+            _ => {
+                for &mesh in batch {
+                    render_pass.draw_indexed_indirect(&self.draws, self.draw_offset(pass, mesh));
+                }
+            },
+        }
+    }
+
+    // reads back the last frame's draws, counting a mesh as drawn when any of its instances were. only meshes
+    // counted(mesh) says the geometry and shadow passes draw are included. this copies the draws out and waits on the
+    // gpu, so frames that nobody asks for stats of don't pay for it
+    pub fn read_stats(&self, device: &Device, queue: &Queue, scene: &Scene, counted: impl Fn(usize) -> bool) -> CullStats {
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: Some("cull stats encoder") });
+        encoder.copy_buffer_to_buffer(&self.draws, 0, &self.readback, 0, self.draws_size);
+        queue.submit(Some(encoder.finish()));
+
+        let slice = self.readback.slice(..);
+        let mapping = slice.map_async(MapMode::Read);
+        device.poll(Maintain::Wait);
+        if block_on(mapping).is_err() {
+            return CullStats::default();
+        }
+        let stats = {
+            let data = slice.get_mapped_range();
+            let draws: &[DrawIndexedIndirect] = bytemuck::cast_slice(&data);
+            let pass_culling = |pass: CullPass| {
+                let mut culling = PassCulling::default();
+                for mesh in (0..self.mesh_count).filter(|&m| counted(m)) {
+                    culling.count(draws[(self.draw_offset(pass, mesh) / DRAW_SIZE) as usize].instance_count > 0);
+                }
+                culling
+            };
+            CullStats {
+                geometry: pass_culling(CullPass::Camera),
+                shadows: scene.lights.iter().enumerate()
+                    .filter(|(_, light)| matches!(light, Light::Punctual { .. }))
+                    .map(|(i, _)| pass_culling(CullPass::Shadow(i)))
+                    .collect(),
+            }
+        };
+        self.readback.unmap();
+        stats
+    }
+}
+
+// where a pass's visible lists start in the visible buffer, in indices. stride is the size of each pass's part in bytes
+fn visible_base(pass: CullPass, stride: BufferAddress) -> u32 {
+    (pass.index() as BufferAddress * stride / std::mem::size_of::<u32>() as BufferAddress) as u32
+}
+
+// index of the draw in a pass's slot, the passes' draws one after another in CullPass order
+fn draw_index(pass: CullPass, slot: usize, mesh_count: usize) -> usize {
+    (pass.index() - 1) * mesh_count + slot
+}
+
+// each mesh's slot, from the meshes in slot order
+fn draw_slots(draw_order: &[usize]) -> Vec<u32> {
+    let mut slots = vec![0; draw_order.len()];
+    for (slot, &mesh) in draw_order.iter().enumerate() {
+        slots[mesh] = slot as u32;
+    }
+    slots
+}
+
+fn batches<'m>(meshes: &'m [usize], slots: &[u32], same: impl Fn(usize, usize) -> bool) -> Vec<&'m [usize]> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    for i in 1..=meshes.len() {
+        let (a, b) = (meshes[i - 1], meshes.get(i).copied());
+        if b.is_none_or(|b| slots[b] != slots[a] + 1 || !same(a, b)) {
+            batches.push(&meshes[start..i]);
+            start = i;
+        }
+    }
+    batches
+}
+
+impl HiZ {
+    #[allow(clippy::too_many_arguments)]
+    fn new(device: &Device, copy_layout: &BindGroupLayout, downsample_layout: &BindGroupLayout, cull_layout: &BindGroupLayout, scene: &Scene, buffers: [&Buffer; 4], depth: &TextureView, width: u32, height: u32) -> Self {
+        let levels = 32 - width.max(height).max(1).leading_zeros();
+        let texture = device.create_texture(&TextureDescriptor {
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: levels,
+            format: TextureFormat::R32Float,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            usage: TextureUsages::STORAGE_BINDING | TextureUsages::TEXTURE_BINDING,
+            label: Some("hi-z texture"),
+        });
+        let view = |base_mip_level, mip_level_count| texture.create_view(&TextureViewDescriptor {
+            label: Some("hi-z"),
+            base_mip_level,
+            mip_level_count,
+            ..Default::default()
+        });
+        let views = (0..levels).map(|i| view(i, NonZeroU32::new(1))).collect::<Vec<_>>();
+
+        let level_bind_groups = (0..levels as usize).map(|i| device.create_bind_group(&BindGroupDescriptor {
+            layout: if i == 0 { copy_layout } else { downsample_layout },
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(if i == 0 { depth } else { &views[i - 1] }),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(&views[i]),
+                },
+            ],
+            label: Some("hi-z level bind group"),
+        })).collect();
+
+        let all_levels = view(0, None);
+        let [meshes, instances, passes, draws] = buffers;
+        let cull_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout: cull_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: scene.mesh_buffers.transforms.buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: meshes.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: instances.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: passes.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: draws.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 5,
+                    resource: scene.mesh_buffers.visible.buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 6,
+                    resource: BindingResource::TextureView(&all_levels),
+                },
+            ],
+            label: Some("cull bind group"),
+        });
+
+        Self {
+            size: (width, height),
+            level_bind_groups,
+            cull_bind_group,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_draws_follow_each_other() {
+        // CullPass::All has no draws, so the camera's come first
+        assert_eq!(draw_index(CullPass::Camera, 0, 5), 0);
+        assert_eq!(draw_index(CullPass::Camera, 4, 5), 4);
+        assert_eq!(draw_index(CullPass::Shadow(0), 0, 5), 5);
+        assert_eq!(draw_index(CullPass::Shadow(2), 3, 5), 18);
+    }
+
+    #[test]
+    fn visible_bases_skip_whole_passes() {
+        // the first part is CullPass::All's, every instance of every mesh
+        assert_eq!(visible_base(CullPass::All, 256), 0);
+        assert_eq!(visible_base(CullPass::Camera, 256), 64);
+        assert_eq!(visible_base(CullPass::Shadow(1), 256), 192);
+    }
+
+    // occluded in cull.wgsl along one axis of a pyramid whose level 0 is size texels across: the level it reads and the
+    // first and last texels
+    fn hiz_footprint(uv_min: f32, uv_max: f32, size: u32) -> (u32, u32, u32) {
+        let levels = 32 - size.leading_zeros();
+        let extent = (uv_max - uv_min) * size as f32;
+        let level = (extent.max(1.0).log2().ceil() as u32).min(levels - 1);
+        let last = (size >> level).max(1) - 1;
+        let texel = |uv: f32| (((uv * size as f32) as u32) >> level).min(last);
+        (level, texel(uv_min), texel(uv_max))
+    }
+
+    // the level 0 texels under a texel, the way downsample in hiz.wgsl builds the levels
+    fn hiz_covers(level: u32, texel: u32, size: u32) -> std::ops::Range<u32> {
+        let last = (size >> level).max(1) - 1;
+        (texel << level)..if texel == last { size } else { (texel + 1) << level }
+    }
+
+    #[test]
+    fn hiz_footprints_cover_the_whole_rectangle() {
+        // 1000 halves to odd sizes, so its levels' last texels cover more than their share
+        let (level, first, last) = hiz_footprint(0.3, 0.55, 1000);
+        assert_eq!((level, first, last), (8, 1, 2));
+        assert_eq!(hiz_covers(level, last, 1000), 512..1000);
+
+        for size in [1, 7, 64, 1000, 1023] {
+            for i in 0..=40 {
+                for j in i..=40 {
+                    let (uv_min, uv_max) = (i as f32 / 40.0, j as f32 / 40.0);
+                    let (level, first, last) = hiz_footprint(uv_min, uv_max, size);
+                    assert!(last - first <= 1);
+                    let pixels = (uv_min * size as f32) as u32..((uv_max * size as f32) as u32 + 1).min(size);
+                    let covered = hiz_covers(level, first, size).start..hiz_covers(level, last, size).end;
+                    assert!(covered.start <= pixels.start && pixels.end <= covered.end, "{}..{} of {} at level {}", uv_min, uv_max, size, level);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn draw_slots_invert_the_draw_order() {
+        assert_eq!(draw_slots(&[2, 0, 3, 1]), [1, 3, 0, 2]);
+        assert_eq!(draw_slots(&[]), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn batches_need_neighboring_draws_and_bindings() {
+        let slots = draw_slots(&[2, 0, 3, 1]);
+        // slots 0 to 3, all bound the same
+        assert_eq!(batches(&[2, 0, 3, 1], &slots, |_, _| true), [&[2, 0, 3, 1][..]]);
+        // slot 1 is skipped between 2 and 3
+        assert_eq!(batches(&[2, 3, 1], &slots, |_, _| true), [&[2][..], &[3, 1][..]]);
+        // out of slot order
+        assert_eq!(batches(&[0, 2], &slots, |_, _| true), [&[0][..], &[2][..]]);
+        assert_eq!(batches(&[2, 0, 3], &slots, |a, b| a + b != 3), [&[2, 0][..], &[3][..]]);
+        assert!(batches(&[], &slots, |_, _| true).is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+use crate::animation::{AnimationClip, NodeTransform, Pose};
+use crate::pose::ScenePose;
+use glam::Mat4;
+use gltf::{buffer::Data, Document};
+use std::cell::RefCell;
+
+// world matrices from the last evaluation, only nodes that moved since get recomputed
+struct WorldCache {
+    local: Vec<Mat4>,
+    world: Vec<Mat4>,
+    dirty: Vec<bool>,
+    // whether the last pose overrode the node's rest transform
+    posed: Vec<bool>,
+}
+
+// the glTF node tree flattened into arrays indexed by node index, built once at load
+pub struct SceneGraph {
+    pub names: Vec<Option<String>>,
+    pub parents: Vec<Option<usize>>,
+    pub rest: Vec<NodeTransform>,
+    // the node's own matrix, which a decomposed rest transform can't always reproduce
+    rest_matrices: Vec<Mat4>,
+    // nodes in the scene, every parent before its children
+    pub order: Vec<usize>,
+    // (clip, channel) pairs that animate each node
+    pub channels: Vec<Vec<(usize, usize)>>,
+    // indices into Scene::meshes of the meshes with an instance on each node
+    pub meshes: Vec<Vec<usize>>,
+    // (joint node, inverse bind matrix) for every joint of every skin
+    pub skins: Vec<Vec<(usize, Mat4)>>,
+    cache: RefCell<WorldCache>,
+}
+
+impl SceneGraph {
+    // mesh_nodes pairs each mesh in Scene::meshes with every node it has instances on
+    pub fn new(document: &Document, clips: &[AnimationClip], mesh_nodes: &[(usize, usize)], skins: Vec<Vec<(usize, Mat4)>>) -> Self {
+        let count = document.nodes().len();
+        let mut parents = vec![None; count];
+        for node in document.nodes() {
+            for child in node.children() {
+                parents[child.index()] = Some(node.index());
+            }
+        }
+
+        let mut order = Vec::with_capacity(count);
+        let mut stack = document.default_scene().or_else(|| document.scenes().next()).map_or_else(Vec::new, |s| s.nodes().collect::<Vec<_>>());
+        stack.reverse();
+        while let Some(node) = stack.pop() {
+            order.push(node.index());
+            stack.extend(node.children().collect::<Vec<_>>().into_iter().rev());
+        }
+
+        let mut channels = vec![Vec::new(); count];
+        for (c, clip) in clips.iter().enumerate() {
+            for (i, channel) in clip.channels.iter().enumerate() {
+                channels[channel.target].push((c, i));
+            }
+        }
+        let mut meshes = vec![Vec::new(); count];
+        for &(mesh, node) in mesh_nodes {
+            meshes[node].push(mesh);
+        }
+
+        let rest_matrices = document.nodes().map(|n| Mat4::from_cols_array_2d(&n.transform().matrix())).collect::<Vec<_>>();
+        let cache = WorldCache {
+            local: rest_matrices.clone(),
+            world: vec![Mat4::IDENTITY; count],
+            dirty: vec![true; count],
+            posed: vec![false; count],
+        };
+        Self {
+            names: document.nodes().map(|n| n.name().map(String::from)).collect(),
+            parents,
+            rest: document.nodes().map(|n| NodeTransform::from_gltf(&n)).collect(),
+            rest_matrices,
+            order,
+            channels,
+            meshes,
+            skins,
+            cache: RefCell::new(cache),
+        }
+    }
+
+    pub fn from_gltf(document: &Document, buffers: &[Data], clips: &[AnimationClip], mesh_nodes: &[(usize, usize)]) -> Self {
+        let skins = document.skins().map(|skin| {
+            skin.reader(|buffer| Some(&buffers[buffer.index()]))
+                .read_inverse_bind_matrices()
+                .into_iter()
+                .flatten()
+                .map(|a| Mat4::from_cols_array_2d(&a))
+                .chain(std::iter::repeat(Mat4::IDENTITY))
+                .zip(skin.joints())
+                .map(|(j, n)| (n.index(), j))
+                .collect::<Vec<(usize, Mat4)>>()
+        }).collect();
+        Self::new(document, clips, mesh_nodes, skins)
+    }
+
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n.as_deref() == Some(name))
+    }
+
+    pub fn is_animated(&self, node: usize) -> bool {
+        !self.channels[node].is_empty()
+    }
+
+    // world matrices for a pose, reusing last time's for every subtree nothing moved in
+    pub fn evaluate(&self, pose: &Pose) -> ScenePose {
+        let mut cache = self.cache.borrow_mut();
+        let WorldCache { local, world, dirty, posed } = &mut *cache;
+        for &node in &self.order {
+            // only nodes posed now or last time can have a different local transform
+            let transform = pose.get(node);
+            if transform.is_some() || posed[node] {
+                let matrix = transform.map_or(self.rest_matrices[node], NodeTransform::matrix);
+                if matrix != local[node] {
+                    local[node] = matrix;
+                    dirty[node] = true;
+                }
+                posed[node] = transform.is_some();
+            }
+            let parent = self.parents[node];
+            if parent.is_some_and(|p| dirty[p]) {
+                dirty[node] = true;
+            }
+            if dirty[node] {
+                world[node] = parent.map_or(Mat4::IDENTITY, |p| world[p]) * local[node];
+            }
+        }
+        dirty.fill(false);
+
+        ScenePose {
+            world: world.clone(),
+            weights: self.rest.iter().enumerate().map(|(i, r)| pose.get(i).unwrap_or(r).weights.clone()).collect(),
+            joints: self.skins.iter().map(|skin| skin.iter().map(|&(joint, inverse_bind)| world[joint] * inverse_bind).collect()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+
+    // root -> (a -> b), and c on its own outside the scene
+    const TREE: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [
+            { "name": "root", "translation": [1, 0, 0], "children": [1] },
+            { "name": "a", "translation": [0, 1, 0], "children": [2] },
+            { "name": "b", "translation": [0, 0, 1] },
+            { "name": "c", "translation": [5, 5, 5] }
+        ]
+    }"#;
+
+    fn tree() -> SceneGraph {
+        let document = gltf::Gltf::from_slice(TREE.as_bytes()).unwrap().document;
+        SceneGraph::new(&document, &[], &[(0, 2), (1, 2), (1, 0)], Vec::new())
+    }
+
+    fn moved(graph: &SceneGraph, node: usize, translation: Vec3) -> Pose {
+        Pose { nodes: [(node, NodeTransform { translation, ..graph.rest[node].clone() })].into_iter().collect() }
+    }
+
+    #[test]
+    fn flattens_hierarchy() {
+        let graph = tree();
+        assert_eq!(graph.parents, vec![None, Some(0), Some(1), None]);
+        assert_eq!(graph.order, vec![0, 1, 2]);
+        assert_eq!(graph.meshes[2], vec![0, 1]);
+        assert_eq!(graph.meshes[0], vec![1]);
+        assert_eq!(graph.find("b"), Some(2));
+        assert_eq!(graph.evaluate(&Pose::default()).world_position(2), Vec3::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn cached_world_matrices_follow_pose_changes() {
+        let graph = tree();
+        assert_eq!(graph.evaluate(&Pose::default()).world_position(2), Vec3::new(1.0, 1.0, 1.0));
+        // moving a parent moves its whole subtree, and dropping the override puts it back
+        assert_eq!(graph.evaluate(&moved(&graph, 1, Vec3::new(0.0, 3.0, 0.0))).world_position(2), Vec3::new(1.0, 3.0, 1.0));
+        assert_eq!(graph.evaluate(&moved(&graph, 1, Vec3::new(0.0, 3.0, 0.0))).world_position(2), Vec3::new(1.0, 3.0, 1.0));
+        assert_eq!(graph.evaluate(&Pose::default()).world_position(2), Vec3::new(1.0, 1.0, 1.0));
+        assert_eq!(graph.evaluate(&moved(&graph, 0, Vec3::ZERO)).world_position(1), Vec3::new(0.0, 1.0, 0.0));
+        // nodes outside the scene aren't placed
+        assert_eq!(graph.evaluate(&Pose::default()).world[3], Mat4::IDENTITY);
+    }
+}
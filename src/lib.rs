@@ -1,8 +1,9 @@
-#![feature(array_chunks)]
-#![feature(map_first_last)]
-
 pub mod context;
 pub mod mesh;
+pub mod arena;
+pub mod culling;
+pub mod gpu_culling;
+pub mod skinning;
 pub mod camera;
 pub mod texture;
 pub mod scene;
@@ -11,3 +12,8 @@ pub mod material;
 pub mod sky;
 pub mod blur;
 pub mod animation;
+pub mod pose;
+pub mod graph;
+pub mod strategy;
+pub mod bench;
+pub mod profiler;
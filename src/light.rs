@@ -0,0 +1,416 @@
+use wgpu::*;
+use wgpu::util::DeviceExt;
+use serde::{Serialize, Deserialize};
+use glam::{Vec4, Vec3, Vec2, Mat4};
+use gltf::khr_lights_punctual;
+use crate::texture::Texture;
+use crate::strategy::BindGroupStrategy;
+use std::f32::consts::PI;
+use std::path::Path;
+use anyhow::Result;
+
+const SHADOW_NEAR: f32 = 1.0;
+const SHADOW_FAR: f32 = 50.0;
+// directional lights have no position, so their shadow map is an orthographic box of this
+// half size around the origin, seen from this far back along the light direction
+const DIRECTIONAL_SHADOW_EXTENT: f32 = 20.0;
+const DIRECTIONAL_SHADOW_DISTANCE: f32 = 25.0;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type")]
+pub enum LightJSON {
+    Point {
+        node: String,
+        position: Vec3,
+        power: Vec3,
+        range: Option<f32>,
+    },
+    Spot {
+        node: String,
+        position: Vec3,
+        direction: Vec3,
+        power: Vec3,
+        inner_cone_angle: f32,
+        outer_cone_angle: f32,
+        range: Option<f32>,
+    },
+    Directional {
+        node: String,
+        direction: Vec3,
+        irradiance: Vec3,
+    },
+    Area {
+        node: String,
+        position: Vec3,
+        power: Vec3,
+        normal: Vec3,
+        up: Vec3,
+        size: Vec2,
+        #[serde(default = "Vec3::default")]
+        u: Vec3,
+        #[serde(default = "Vec3::default")]
+        v: Vec3,
+    },
+    Ambient {
+        node: String,
+        radiance: Vec3,
+        range: Option<f32>,
+    },
+}
+
+impl LightJSON {
+    pub fn from_file(filename: impl AsRef<Path>) -> Result<Vec<Self>> {
+        let json_str = std::fs::read_to_string(filename)?;
+        Ok(serde_json::from_str(&json_str)?)
+    }
+
+    // a KHR_lights_punctual light in its node's local space, apply_matrix moves it into the world.
+    // intensities are candela (lux for directional), power here is radiant intensity over the
+    // whole sphere, hence the 4 pi
+    pub fn from_gltf(light: &khr_lights_punctual::Light, node: String) -> Self {
+        let color = Vec3::from(light.color()) * light.intensity();
+        match light.kind() {
+            khr_lights_punctual::Kind::Point => LightJSON::Point {
+                node,
+                position: Vec3::ZERO,
+                power: 4.0 * PI * color,
+                range: light.range(),
+            },
+            khr_lights_punctual::Kind::Spot { inner_cone_angle, outer_cone_angle } => LightJSON::Spot {
+                node,
+                position: Vec3::ZERO,
+                direction: -Vec3::Z,
+                power: 4.0 * PI * color,
+                inner_cone_angle,
+                outer_cone_angle,
+                range: light.range(),
+            },
+            khr_lights_punctual::Kind::Directional => LightJSON::Directional {
+                node,
+                direction: -Vec3::Z,
+                irradiance: color,
+            },
+        }
+    }
+
+    // the sidecar's lights, then the glTF's punctual lights on nodes the sidecar doesn't have a light for
+    pub fn merge(mut sidecar: Vec<Self>, punctual: Vec<Self>) -> Vec<Self> {
+        let overridden = punctual.iter().map(|light| sidecar.iter().any(|l| l.get_node() == light.get_node())).collect::<Vec<_>>();
+        sidecar.extend(punctual.into_iter().zip(overridden).filter(|(_, overridden)| !overridden).map(|(light, _)| light));
+        sidecar
+    }
+
+    pub fn get_node(&self) -> &str {
+        match self {
+            LightJSON::Point { node, ..} | LightJSON::Spot { node, .. } | LightJSON::Directional { node, .. } | LightJSON::Area { node, .. } | LightJSON::Ambient { node, .. } => node
+        }
+    }
+
+    pub fn apply_matrix(&mut self, mat: Mat4) {
+        match self {
+            LightJSON::Point { position, .. } => {
+                *position = mat.transform_point3(*position);
+            },
+            LightJSON::Spot { position, direction, .. } => {
+                *position = mat.transform_point3(*position);
+                *direction = mat.transform_vector3(*direction).normalize();
+            },
+            LightJSON::Directional { direction, .. } => {
+                *direction = mat.transform_vector3(*direction).normalize();
+            },
+            LightJSON::Area { position, normal, up, u, v, .. } => {
+                *position = mat.transform_point3(*position);
+                *normal = mat.transform_vector3(*normal);
+                *up = mat.transform_vector3(*up);
+
+                // compute light basis here to not do later
+                *u = up.cross(*normal).normalize();
+                *v = normal.cross(*u);
+            },
+            LightJSON::Ambient { .. } => (),
+        }
+    }
+
+    pub fn to_light(&self, device: &Device, layout: &BindGroupLayout, texture_layout: &BindGroupLayout) -> Light {
+        match *self {
+            LightJSON::Point { position, power, range, .. } => Light::new_point(position, power, range, device, layout, texture_layout),
+            LightJSON::Area { position, power, .. } => Light::new_point(position, power, None, device, layout, texture_layout),
+            LightJSON::Spot { position, direction, power, inner_cone_angle, outer_cone_angle, range, .. } => {
+                Light::new_spot(position, direction, power, inner_cone_angle, outer_cone_angle, range, device, layout, texture_layout)
+            },
+            LightJSON::Directional { direction, irradiance, .. } => Light::new_directional(direction, irradiance, device, layout, texture_layout),
+            LightJSON::Ambient { radiance, range, .. } => Light::new_ambient(radiance, range, device, layout),
+        }
+    }
+}
+
+// the buffer and raw slice are kept around so strategies that rebuild
+// bind groups every frame have something to build them from.
+// point, spot and directional lights are all punctual, they only differ in their uniforms
+#[allow(clippy::large_enum_variant)]
+pub enum Light {
+    Punctual { bind_group: BindGroup, buffer: Buffer, slice: [Vec4; 12], texture: Texture },
+    Ambient { bind_group: BindGroup, buffer: Buffer, slice: [f32; 4] },
+}
+
+impl Light {
+    pub fn new_point(position: Vec3, power: Vec3, range: Option<f32>, device: &Device, layout: &BindGroupLayout, texture_layout: &BindGroupLayout) -> Self {
+        let view_mat = point_shadow_view(position);
+        let proj_mat = Mat4::perspective_rh(1.0, 1.0, SHADOW_NEAR, SHADOW_FAR);
+
+        // a cone wider than the whole sphere, so the spot falloff is always 1
+        let cone = Vec4::new(-2.0, -1.0, range.unwrap_or(0.0), 0.0);
+        Self::new_punctual(proj_mat, view_mat, power, position.extend(1.0), Vec3::ZERO, cone, device, layout, texture_layout)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_spot(position: Vec3, direction: Vec3, power: Vec3, inner_cone_angle: f32, outer_cone_angle: f32, range: Option<f32>, device: &Device, layout: &BindGroupLayout, texture_layout: &BindGroupLayout) -> Self {
+        let view_mat = Mat4::look_at_rh(position, position + direction, shadow_up(direction));
+        let fov = (2.0 * outer_cone_angle).clamp(0.01, PI - 0.01);
+        let proj_mat = Mat4::perspective_rh(fov, 1.0, SHADOW_NEAR, SHADOW_FAR);
+
+        let cone = Vec4::new(outer_cone_angle.cos(), inner_cone_angle.cos(), range.unwrap_or(0.0), 0.0);
+        Self::new_punctual(proj_mat, view_mat, power, position.extend(1.0), direction, cone, device, layout, texture_layout)
+    }
+
+    // irradiance goes where power is for the other lights, a w of 0 on the position marks it directional
+    pub fn new_directional(direction: Vec3, irradiance: Vec3, device: &Device, layout: &BindGroupLayout, texture_layout: &BindGroupLayout) -> Self {
+        let eye = -direction * DIRECTIONAL_SHADOW_DISTANCE;
+        let view_mat = Mat4::look_at_rh(eye, Vec3::ZERO, shadow_up(direction));
+        let e = DIRECTIONAL_SHADOW_EXTENT;
+        let proj_mat = Mat4::orthographic_rh(-e, e, -e, e, SHADOW_NEAR, DIRECTIONAL_SHADOW_DISTANCE + e);
+
+        let cone = Vec4::new(-2.0, -1.0, 0.0, 0.0);
+        Self::new_punctual(proj_mat, view_mat, irradiance, Vec4::ZERO, direction, cone, device, layout, texture_layout)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn new_punctual(proj_mat: Mat4, view_mat: Mat4, power: Vec3, position: Vec4, direction: Vec3, cone: Vec4, device: &Device, layout: &BindGroupLayout, texture_layout: &BindGroupLayout) -> Self {
+        // cone is (cos outer angle, cos inner angle, range or 0 for none, unused)
+        let slice = [
+            proj_mat.col(0),
+            proj_mat.col(1),
+            proj_mat.col(2),
+            proj_mat.col(3),
+            view_mat.col(0),
+            view_mat.col(1),
+            view_mat.col(2),
+            view_mat.col(3),
+            power.extend(1.0),
+            position,
+            direction.extend(0.0),
+            cone,
+        ];
+
+        let buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("light buffer"),
+            contents: bytemuck::cast_slice(&slice),
+            usage: BufferUsages::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }
+            ],
+            label: Some("light bind group"),
+        });
+
+        let texture = Texture::create_window_texture(device, texture_layout, TextureFormat::Depth32Float, Some(CompareFunction::LessEqual), 1024, 1024);
+
+        Self::Punctual {
+            bind_group,
+            buffer,
+            slice,
+            texture,
+        }
+    }
+
+    pub fn new_ambient(radiance: Vec3, range: Option<f32>, device: &Device, layout: &BindGroupLayout) -> Self {
+
+        let slice = [
+            radiance.x,
+            radiance.y,
+            radiance.z,
+            range.unwrap_or(0.0),
+        ];
+
+        let buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("ambient light buffer"),
+            contents: bytemuck::cast_slice(&slice),
+            usage: BufferUsages::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }
+            ],
+            label: Some("ambient light label"),
+        });
+
+        Self::Ambient {
+            bind_group,
+            buffer,
+            slice,
+        }
+    }
+
+    // projection times view of a punctual light's shadow map
+    pub fn view_proj(&self) -> Option<Mat4> {
+        match self {
+            Light::Punctual { slice, .. } => Some(Mat4::from_cols(slice[0], slice[1], slice[2], slice[3]) * Mat4::from_cols(slice[4], slice[5], slice[6], slice[7])),
+            Light::Ambient { .. } => None,
+        }
+    }
+
+    // bind group for this frame under strategies that don't reuse the one made at load
+    pub fn frame_bind_group(&self, strategy: BindGroupStrategy, device: &Device, layout: &BindGroupLayout) -> Option<BindGroup> {
+        let (buffer, contents): (&Buffer, &[u8]) = match self {
+            Light::Punctual { buffer, slice, .. } => (buffer, bytemuck::cast_slice(slice)),
+            Light::Ambient { buffer, slice, .. } => (buffer, bytemuck::cast_slice(slice)),
+        };
+        let frame_buffer;
+        let buffer = match strategy {
+            BindGroupStrategy::Lights => buffer,
+            BindGroupStrategy::Lights2 => {
+                frame_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+                    label: Some("light buffer"),
+                    contents,
+                    usage: BufferUsages::UNIFORM,
+                });
+                &frame_buffer
+            },
+            _ => return None,
+        };
+        Some(device.create_bind_group(&BindGroupDescriptor {
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }
+            ],
+            label: Some("light bind group"),
+        }))
+    }
+}
+
+// a point light's single shadow map looks at the origin, or straight down from the origin itself
+fn point_shadow_view(position: Vec3) -> Mat4 {
+    let direction = if position.length_squared() > 0.0 { -position } else { -Vec3::Y };
+    Mat4::look_at_rh(position, position + direction, shadow_up(direction))
+}
+
+// any up vector that isn't parallel to the light direction
+fn shadow_up(direction: Vec3) -> Vec3 {
+    if direction.normalize().y.abs() > 0.99 {
+        Vec3::Z
+    } else {
+        Vec3::Y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a point light, a spot light and a directional light, each on a node of the same name
+    const PUNCTUAL: &str = r#"{
+        "asset": { "version": "2.0" },
+        "extensionsUsed": ["KHR_lights_punctual"],
+        "extensions": { "KHR_lights_punctual": { "lights": [
+            { "type": "point", "color": [1.0, 0.5, 0.0], "intensity": 2.0, "range": 10.0 },
+            { "type": "spot", "intensity": 3.0, "spot": { "innerConeAngle": 0.25, "outerConeAngle": 0.5 } },
+            { "type": "directional", "color": [0.0, 0.0, 1.0], "intensity": 4.0 }
+        ] } },
+        "nodes": [
+            { "name": "point", "extensions": { "KHR_lights_punctual": { "light": 0 } } },
+            { "name": "spot", "extensions": { "KHR_lights_punctual": { "light": 1 } } },
+            { "name": "sun", "extensions": { "KHR_lights_punctual": { "light": 2 } } }
+        ]
+    }"#;
+
+    fn punctual_lights() -> Vec<LightJSON> {
+        let gltf = gltf::Gltf::from_slice(PUNCTUAL.as_bytes()).unwrap();
+        gltf.nodes().map(|node| LightJSON::from_gltf(&node.light().unwrap(), node.name().unwrap().to_string())).collect()
+    }
+
+    #[test]
+    fn punctual_lights_become_light_json() {
+        let lights = punctual_lights();
+        match &lights[0] {
+            // candela over the whole sphere
+            LightJSON::Point { node, position, power, range } => {
+                assert_eq!(node, "point");
+                assert_eq!(*position, Vec3::ZERO);
+                assert!(power.abs_diff_eq(4.0 * PI * Vec3::new(2.0, 1.0, 0.0), 1e-5));
+                assert_eq!(*range, Some(10.0));
+            },
+            light => panic!("Expected a point light, got {:?}", light),
+        }
+        match &lights[1] {
+            // white when there's no color, pointing down -z until its node moves it
+            LightJSON::Spot { direction, power, inner_cone_angle, outer_cone_angle, range, .. } => {
+                assert_eq!(*direction, -Vec3::Z);
+                assert!(power.abs_diff_eq(Vec3::splat(4.0 * PI * 3.0), 1e-5));
+                assert_eq!((*inner_cone_angle, *outer_cone_angle), (0.25, 0.5));
+                assert_eq!(*range, None);
+            },
+            light => panic!("Expected a spot light, got {:?}", light),
+        }
+        match &lights[2] {
+            // lux is already irradiance, so no 4 pi
+            LightJSON::Directional { direction, irradiance, .. } => {
+                assert_eq!(*direction, -Vec3::Z);
+                assert_eq!(*irradiance, Vec3::new(0.0, 0.0, 4.0));
+            },
+            light => panic!("Expected a directional light, got {:?}", light),
+        }
+    }
+
+    #[test]
+    fn node_matrices_move_punctual_lights() {
+        let mut lights = punctual_lights();
+        let mat = Mat4::from_translation(Vec3::new(1.0, 2.0, 3.0)) * Mat4::from_rotation_x(-PI / 2.0);
+        for light in &mut lights {
+            light.apply_matrix(mat);
+        }
+        assert!(matches!(lights[0], LightJSON::Point { position, .. } if position == Vec3::new(1.0, 2.0, 3.0)));
+        // -z turned a quarter about x points down
+        assert!(matches!(lights[1], LightJSON::Spot { direction, .. } if direction.abs_diff_eq(-Vec3::Y, 1e-5)));
+        assert!(matches!(lights[2], LightJSON::Directional { direction, .. } if direction.abs_diff_eq(-Vec3::Y, 1e-5)));
+    }
+
+    #[test]
+    fn sidecar_lights_override_punctual_ones() {
+        let sidecar = vec![
+            LightJSON::Ambient { node: "sky".to_string(), radiance: Vec3::ONE, range: None },
+            LightJSON::Point { node: "spot".to_string(), position: Vec3::Y, power: Vec3::ONE, range: None },
+        ];
+        let lights = LightJSON::merge(sidecar, punctual_lights());
+        let nodes = lights.iter().map(LightJSON::get_node).collect::<Vec<_>>();
+        assert_eq!(nodes, ["sky", "spot", "point", "sun"]);
+        // the sidecar's light is the one kept for the node both have
+        assert!(matches!(lights[1], LightJSON::Point { .. }));
+        assert_eq!(LightJSON::merge(Vec::new(), punctual_lights()).len(), 3);
+    }
+
+    #[test]
+    fn point_shadows_work_straight_above_the_origin() {
+        for position in [Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -5.0, 0.0), Vec3::ZERO, Vec3::new(3.0, 4.0, 0.0)] {
+            let view = point_shadow_view(position);
+            assert!(view.is_finite(), "{:?} gave {:?}", position, view);
+            assert!(view.transform_point3(position).abs_diff_eq(Vec3::ZERO, 1e-5));
+        }
+        // and still look at the origin
+        let view = point_shadow_view(Vec3::new(0.0, 5.0, 0.0));
+        assert!(view.transform_point3(Vec3::ZERO).abs_diff_eq(Vec3::new(0.0, 0.0, -5.0), 1e-5));
+    }
+}
@@ -0,0 +1,305 @@
+use winit::{
+    event_loop::{EventLoop, ControlFlow},
+    event::{Event, WindowEvent, ElementState, DeviceEvent, VirtualKeyCode, KeyboardInput},
+    window::Window,
+};
+use anyhow::{Result, anyhow};
+use futures::executor::block_on;
+use rasterization::context::Context;
+use rasterization::strategy::BindGroupStrategy;
+use rasterization::bench::{self, BenchConfig};
+use rasterization::scene::{Scene, LoadOptions};
+use glam::{Vec3, Mat3};
+use std::time::{Instant, Duration};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HEADLESS_WIDTH: u32 = 1280;
+const HEADLESS_HEIGHT: u32 = 720;
+
+const USAGE: &str = "Usage: rasterization [--strategy clean|lights|lights2|mesh|sky] [--headless out.ppm] [--lights lights.json] [--max-influences 4|8] [--clip NAME] [--crossfade SECONDS] path/to/scene.glb|gltf
+       rasterization bench [--warmup N] [--frames N] [--replicates N] [--strategy NAME]... [--out results/NAME] [path/to/scene.glb]...";
+
+enum Command {
+    View {
+        file_path: String,
+        options: LoadOptions,
+        clip: Option<String>,
+        crossfade: f32,
+        strategy: BindGroupStrategy,
+        headless: Option<String>,
+    },
+    Bench {
+        config: BenchConfig,
+        out: PathBuf,
+    },
+}
+
+impl Command {
+    fn parse() -> Result<Self> {
+        let mut args = std::env::args().skip(1).peekable();
+        if args.peek().map(String::as_str) == Some("bench") {
+            args.next();
+            return Self::parse_bench(args);
+        }
+
+        let mut file_path = None;
+        let mut options = LoadOptions::default();
+        let mut clip = None;
+        let mut crossfade = 0.3;
+        let mut strategy = BindGroupStrategy::default();
+        let mut headless = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--strategy" => {
+                    strategy = args.next().ok_or(anyhow!("--strategy needs a value"))?.parse()?;
+                },
+                "--lights" => {
+                    options.lights_path = Some(PathBuf::from(args.next().ok_or(anyhow!("--lights needs a path"))?));
+                },
+                "--max-influences" => {
+                    options.max_influences = Some(args.next().ok_or(anyhow!("--max-influences needs a value"))?.parse()?);
+                },
+                "--clip" => {
+                    clip = Some(args.next().ok_or(anyhow!("--clip needs a clip name"))?);
+                },
+                "--crossfade" => {
+                    crossfade = args.next().ok_or(anyhow!("--crossfade needs a duration in seconds"))?.parse()?;
+                },
+                "--headless" => {
+                    headless = Some(args.next().ok_or(anyhow!("--headless needs an output path"))?);
+                },
+                _ => file_path = Some(arg),
+            }
+        }
+        let file_path = file_path.ok_or(anyhow!("Please provide a path to a glb or gltf file."))?;
+        Ok(Self::View { file_path, options, clip, crossfade, strategy, headless })
+    }
+
+    fn parse_bench(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut config = BenchConfig {
+            warmup: 60,
+            frames: 600,
+            replicates: 1,
+            width: HEADLESS_WIDTH,
+            height: HEADLESS_HEIGHT,
+            strategies: Vec::new(),
+            scenes: Vec::new(),
+        };
+        let mut out = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--warmup" => config.warmup = args.next().ok_or(anyhow!("--warmup needs a value"))?.parse()?,
+                "--frames" => config.frames = args.next().ok_or(anyhow!("--frames needs a value"))?.parse()?,
+                "--replicates" => config.replicates = args.next().ok_or(anyhow!("--replicates needs a value"))?.parse()?,
+                "--strategy" => config.strategies.push(args.next().ok_or(anyhow!("--strategy needs a value"))?.parse()?),
+                "--out" => out = Some(PathBuf::from(args.next().ok_or(anyhow!("--out needs a path"))?)),
+                _ => config.scenes.push(PathBuf::from(arg)),
+            }
+        }
+        if config.strategies.is_empty() {
+            config.strategies = BindGroupStrategy::ALL.to_vec();
+        }
+        if config.scenes.is_empty() {
+            config.scenes.push(PathBuf::from("resources/scenes/bunnyscene.glb"));
+        }
+        let out = match out {
+            Some(out) => out,
+            None => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+                PathBuf::from(format!("results/bench-{}", now))
+            },
+        };
+        Ok(Self::Bench { config, out })
+    }
+}
+
+fn main() -> Result<()> {
+
+    #[cfg(debug_assertions)]
+    env_logger::init(); // enable logging for vulkan validation layers
+
+    let (file_path, options, clip, crossfade, strategy) = match Command::parse() {
+        Ok(Command::View { file_path, options, clip, crossfade, strategy, headless: None }) => (file_path, options, clip, crossfade, strategy),
+        Ok(Command::View { file_path, options, clip, strategy, headless: Some(out_path), .. }) => {
+            return render_headless(&file_path, &options, clip.as_deref(), strategy, out_path);
+        },
+        Ok(Command::Bench { config, out }) => return run_bench(&config, &out),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        },
+    };
+
+    let event_loop = EventLoop::new();
+    let window = Window::new(&event_loop)?;
+    let mut state = block_on(Context::new(&window, file_path, &options, strategy))?;
+    if let Some(name) = clip {
+        select_clip_by_name(&mut state.scene, &name)?;
+    }
+    let mut clicking = false;
+    let mut x_accel = 0.0;
+    let mut y_accel = 0.0;
+    // this time stuff is a bit clunky rn, I wonder if there's a more elegant way
+    let mut start_time = Instant::now();
+    let mut pause_time: Option<Instant> = None;
+    let mut elapsed = 0.0;
+    event_loop.run(move |event, _, control_flow| {
+        if pause_time.is_none() {
+            let now = Instant::now();
+            if let Some(duration) = now.checked_duration_since(start_time) {
+                elapsed = duration.as_secs_f32();
+            } else {
+                start_time = Instant::now();
+                elapsed = 0.0;
+            }
+        }
+        match event {
+            Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => *control_flow = ControlFlow::Exit,
+            Event::WindowEvent { event: WindowEvent::Resized(size), .. } => state.resize(size.width, size.height),
+            Event::WindowEvent { event: WindowEvent::ScaleFactorChanged { new_inner_size, .. }, .. } => state.resize(new_inner_size.width, new_inner_size.height),
+            Event::WindowEvent { event: WindowEvent::MouseInput { state: ElementState::Pressed, .. }, .. } => clicking = true,
+            Event::WindowEvent { event: WindowEvent::MouseInput { state: ElementState::Released, .. }, .. } => clicking = false,
+            Event::WindowEvent { event: WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(VirtualKeyCode::Space), state: ElementState::Released, .. }, .. }, .. } => {
+                if let Some(t) = pause_time {
+                    start_time += t.elapsed();
+                    pause_time = None;
+                } else {
+                    pause_time = Some(Instant::now());
+                }
+            },
+            Event::WindowEvent { event: WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(VirtualKeyCode::R), state: ElementState::Released, .. }, .. }, .. } => {
+                start_time = Instant::now();
+                pause_time = None;
+                let clip = state.scene.mixer.current();
+                state.scene.select_clip(clip, 0.0).ok();
+            },
+            Event::WindowEvent { event: WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(VirtualKeyCode::P), state: ElementState::Released, .. }, .. }, .. } => {
+                state.scene.camera.toggle_projection();
+            },
+            Event::WindowEvent { event: WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(VirtualKeyCode::Left), state: ElementState::Pressed, .. }, .. }, .. } => {
+                start_time += Duration::new(0, 50000000);
+                if let Some(t) = pause_time {
+                    elapsed = (start_time.elapsed().saturating_sub(t.elapsed())).as_secs_f32();
+                }
+            },
+            Event::WindowEvent { event: WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(VirtualKeyCode::Right), state: ElementState::Pressed, .. }, .. }, .. } => {
+                start_time -= Duration::new(0, 50000000);
+                if let Some(t) = pause_time {
+                    elapsed = (start_time.elapsed().saturating_sub(t.elapsed())).as_secs_f32();
+                }
+            },
+            Event::WindowEvent { event: WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(VirtualKeyCode::L), state: ElementState::Released, .. }, .. }, .. } => {
+                print_clips(&state.scene);
+            },
+            Event::WindowEvent { event: WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(key @ (VirtualKeyCode::Key0 | VirtualKeyCode::Key1 | VirtualKeyCode::Key2 | VirtualKeyCode::Key3 | VirtualKeyCode::Key4 | VirtualKeyCode::Key5 | VirtualKeyCode::Key6 | VirtualKeyCode::Key7 | VirtualKeyCode::Key8 | VirtualKeyCode::Key9)), state: ElementState::Released, .. }, .. }, .. } => {
+                // 1 to 9 fade over to a clip from its start, 0 fades back to the rest pose
+                let digit = key as usize - VirtualKeyCode::Key1 as usize;
+                let index = if key == VirtualKeyCode::Key0 { None } else { Some(digit) };
+                if state.scene.crossfade_to(index, elapsed, crossfade).is_ok() {
+                    print_clips(&state.scene);
+                }
+            },
+            Event::WindowEvent { event: WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(VirtualKeyCode::M), state: ElementState::Released, .. }, .. }, .. } => {
+                if let Some(clip) = state.scene.clip_mut() {
+                    clip.mode = clip.mode.next();
+                    println!("{}: {}", clip.name, clip.mode);
+                }
+            },
+            Event::WindowEvent { event: WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(key @ (VirtualKeyCode::Up | VirtualKeyCode::Down)), state: ElementState::Pressed, .. }, .. }, .. } => {
+                if let Some(clip) = state.scene.clip_mut() {
+                    clip.step_speed(if key == VirtualKeyCode::Up { 0.25 } else { -0.25 });
+                    println!("{}: {}x", clip.name, clip.speed);
+                }
+            },
+            Event::DeviceEvent { event: DeviceEvent::MouseMotion { delta: (x, y) }, .. } if clicking => {
+                x_accel = x;
+                y_accel = y;
+            },
+
+            Event::MainEventsCleared => {
+                let mut move_mat = Mat3::from_rotation_y((-x_accel as f32).to_radians());
+                let max = Vec3::new(0.0, -y_accel as f32, 0.0);
+                let angle = f32::atan2(state.scene.camera.eye.cross(max).length(), state.scene.camera.eye.dot(max));
+                if angle < 2.8 {
+                    let a = state.scene.camera.eye + state.scene.camera.target;
+                    let axis = a.cross(Vec3::new(0.0, 1.0, 0.0)).normalize();
+                    move_mat *= Mat3::from_axis_angle(axis.normalize(), (y_accel as f32).to_radians());
+                }
+                state.scene.camera.update(&state.queue, move_mat);
+
+                x_accel *= 0.95;
+                if (-0.01..0.01).contains(&x_accel) {
+                    x_accel = 0.0;
+                }
+
+                y_accel *= 0.95;
+                if (-0.01..0.01).contains(&y_accel) {
+                    y_accel = 0.0;
+                }
+
+                if state.render(elapsed).is_err() {
+                    *control_flow = ControlFlow::Exit;
+                }
+            },
+            _ => {},
+        }
+
+    })
+}
+
+// renders a single frame without a window and writes it out as a binary ppm
+fn render_headless(file_path: &str, options: &LoadOptions, clip: Option<&str>, strategy: BindGroupStrategy, out_path: impl AsRef<Path>) -> Result<()> {
+    let mut state = block_on(Context::new_headless_with(HEADLESS_WIDTH, HEADLESS_HEIGHT, file_path, options, strategy))?;
+    if let Some(name) = clip {
+        select_clip_by_name(&mut state.scene, name)?;
+    }
+    state.render(0.0)?;
+    let pixels = block_on(state.read_frame())?;
+
+    let mut ppm = format!("P6\n{} {}\n255\n", state.width, state.height).into_bytes();
+    ppm.extend(pixels.chunks(4).flat_map(|p| [p[0], p[1], p[2]]));
+    std::fs::write(out_path, ppm)?;
+    Ok(())
+}
+
+fn select_clip_by_name(scene: &mut Scene, name: &str) -> Result<()> {
+    let index = scene.find_clip(name).ok_or_else(|| anyhow!("There's no animation clip called {}, try one of: {}", name, scene.clip_names().collect::<Vec<_>>().join(", ")))?;
+    scene.select_clip(Some(index), 0.0)
+}
+
+// one line per clip, the active one marked with a *
+fn print_clips(scene: &Scene) {
+    if scene.clips.is_empty() {
+        println!("No animation clips");
+    }
+    for (i, clip) in scene.clips.iter().enumerate() {
+        let marker = if scene.mixer.current() == Some(i) { '*' } else { ' ' };
+        println!("{} {}: {} ({:.2}s to {:.2}s, {}, {}x)", marker, i + 1, clip.name, clip.start, clip.end, clip.mode, clip.speed);
+    }
+}
+
+// writes `out`.json with the full report and `out`.result in the format graph.py reads
+fn run_bench(config: &BenchConfig, out: &Path) -> Result<()> {
+    let report = block_on(bench::run(config))?;
+    for result in &report.results {
+        if let Some(stats) = result.stats {
+            println!(
+                "{} {} #{}: mean {:.0}ns median {:.0}ns p95 {:.0}ns p99 {:.0}ns cv {:.3}",
+                result.strategy, result.scene, result.replicate, stats.mean, stats.median, stats.p95, stats.p99, stats.cv,
+            );
+        }
+        for pass in &result.passes {
+            if let Some(stats) = pass.stats {
+                println!("    {:<12} mean {:.0}ns p95 {:.0}ns", pass.name, stats.mean, stats.p95);
+            }
+        }
+        let culling = &result.culling;
+        let shadows = culling.shadows.iter().map(|s| format!("{}/{}", s.drawn, s.drawn + s.culled)).collect::<Vec<_>>();
+        println!("    meshes drawn: geometry {}/{}, shadows [{}]", culling.geometry.drawn, culling.geometry.drawn + culling.geometry.culled, shadows.join(", "));
+    }
+    report.write_json(out.with_extension("json"))?;
+    report.write_legacy(out.with_extension("result"))?;
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use wgpu::*;
+use wgpu::util::DeviceExt;
+use glam::Vec3;
+use crevice::std140::{AsStd140, Std140};
+use gltf::material::AlphaMode;
+use crate::texture::ImageTexture;
+use std::rc::Rc;
+
+#[derive(AsStd140)]
+pub struct Material {
+    roughness: f32,
+    k_s: f32,
+    eta: f32,
+    diffuse: Vec3,
+    normal_scale: f32,
+    occlusion_strength: f32,
+    metallic: f32,
+    opacity: f32,
+    // fragments with less opacity than this are discarded, 0 unless the material is masked
+    alpha_cutoff: f32,
+    // emissive factor with KHR_materials_emissive_strength already applied
+    emissive: Vec3,
+}
+
+impl Material {
+    // roughness is glTF's perceptual roughness, the shaders square it into the GGX alpha
+    pub fn new(roughness: f32, k_s: f32, eta: f32, diffuse: Vec3) -> Self {
+        Self {
+            roughness,
+            k_s,
+            eta,
+            diffuse,
+            normal_scale: 1.0,
+            occlusion_strength: 1.0,
+            metallic: 0.0,
+            opacity: 1.0,
+            alpha_cutoff: 0.0,
+            emissive: Vec3::ZERO,
+        }
+    }
+
+    pub fn from_gltf(material: &gltf::Material, emissive_strength: f32) -> Self {
+        let pbr = material.pbr_metallic_roughness();
+        Self {
+            normal_scale: material.normal_texture().map_or(1.0, |t| t.scale()),
+            occlusion_strength: material.occlusion_texture().map_or(1.0, |t| t.strength()),
+            metallic: pbr.metallic_factor(),
+            opacity: pbr.base_color_factor()[3],
+            alpha_cutoff: match material.alpha_mode() {
+                AlphaMode::Mask => material.alpha_cutoff().unwrap_or(0.5),
+                AlphaMode::Opaque | AlphaMode::Blend => 0.0,
+            },
+            emissive: Vec3::from(material.emissive_factor()) * emissive_strength,
+            ..Self::new(pbr.roughness_factor(), 1.0, 1.5, Vec3::from_slice(&pbr.base_color_factor()))
+        }
+    }
+
+    pub fn to_buffer(&self, device: &Device) -> Buffer {
+        device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("material buffer"),
+            contents: self.as_std140().as_bytes(),
+            usage: BufferUsages::UNIFORM,
+        })
+    }
+}
+
+// white and flat normal textures stand in for any texture a material doesn't have
+pub struct DefaultTextures {
+    pub white: Rc<ImageTexture>,
+    pub white_srgb: Rc<ImageTexture>,
+    pub normal: Rc<ImageTexture>,
+}
+
+impl DefaultTextures {
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        Self {
+            white: Rc::new(ImageTexture::from_color(device, queue, [255, 255, 255, 255], false)),
+            white_srgb: Rc::new(ImageTexture::from_color(device, queue, [255, 255, 255, 255], true)),
+            normal: Rc::new(ImageTexture::from_color(device, queue, [128, 128, 255, 255], false)),
+        }
+    }
+}
+
+// a material's uniforms along with every texture it samples, textures are shared between materials
+pub struct GpuMaterial {
+    pub buffer: Buffer,
+    pub alpha_mode: AlphaMode,
+    pub base_color: Rc<ImageTexture>,
+    pub metallic_roughness: Rc<ImageTexture>,
+    pub normal: Rc<ImageTexture>,
+    pub occlusion: Rc<ImageTexture>,
+    pub emissive: Rc<ImageTexture>,
+}
+
+impl GpuMaterial {
+    pub fn new(material: &Material, device: &Device, defaults: &DefaultTextures) -> Self {
+        Self {
+            buffer: material.to_buffer(device),
+            alpha_mode: AlphaMode::Opaque,
+            base_color: defaults.white_srgb.clone(),
+            metallic_roughness: defaults.white.clone(),
+            normal: defaults.normal.clone(),
+            occlusion: defaults.white.clone(),
+            emissive: defaults.white_srgb.clone(),
+        }
+    }
+
+    // texture hands back the uploaded gltf texture, as srgb or linear
+    pub fn from_gltf(material: &gltf::Material, emissive_strength: f32, device: &Device, defaults: &DefaultTextures, mut texture: impl FnMut(&gltf::Texture, bool) -> Rc<ImageTexture>) -> Self {
+        let pbr = material.pbr_metallic_roughness();
+        let gpu = Self::new(&Material::from_gltf(material, emissive_strength), device, defaults);
+        Self {
+            base_color: pbr.base_color_texture().map_or(gpu.base_color.clone(), |t| texture(&t.texture(), true)),
+            metallic_roughness: pbr.metallic_roughness_texture().map_or(gpu.metallic_roughness.clone(), |t| texture(&t.texture(), false)),
+            normal: material.normal_texture().map_or(gpu.normal.clone(), |t| texture(&t.texture(), false)),
+            occlusion: material.occlusion_texture().map_or(gpu.occlusion.clone(), |t| texture(&t.texture(), false)),
+            emissive: material.emissive_texture().map_or(gpu.emissive.clone(), |t| texture(&t.texture(), true)),
+            alpha_mode: material.alpha_mode(),
+            ..gpu
+        }
+    }
+
+    pub fn textures(&self) -> [&ImageTexture; 5] {
+        [&self.base_color, &self.metallic_roughness, &self.normal, &self.occlusion, &self.emissive]
+    }
+}
+
+// the gltf crate doesn't know KHR_materials_emissive_strength and drops it, so it's read out of the raw json.
+// one strength per material, 1 where the extension isn't used or its strength isn't a number of at least 0.
+// root is the file's whole json
+pub fn emissive_strengths(root: &serde_json::Value) -> Vec<f32> {
+    root["materials"].as_array().map_or_else(Vec::new, |materials| {
+        materials.iter()
+            .map(|m| m["extensions"]["KHR_materials_emissive_strength"]["emissiveStrength"].as_f64().filter(|s| *s >= 0.0).unwrap_or(1.0) as f32)
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMISSIVE: &str = r#"{
+        "asset": { "version": "2.0" },
+        "extensionsUsed": ["KHR_materials_emissive_strength"],
+        "materials": [
+            { "emissiveFactor": [1, 1, 1], "extensions": { "KHR_materials_emissive_strength": { "emissiveStrength": 5.5 } } },
+            { "emissiveFactor": [1, 1, 1] },
+            { "extensions": { "KHR_materials_emissive_strength": { "emissiveStrength": "bright" } } },
+            { "extensions": { "KHR_materials_emissive_strength": { "emissiveStrength": -2 } } },
+            { "extensions": { "KHR_materials_emissive_strength": {} } }
+        ]
+    }"#;
+
+    #[test]
+    fn reads_emissive_strengths() {
+        let root: serde_json::Value = serde_json::from_str(EMISSIVE).unwrap();
+        assert_eq!(emissive_strengths(&root), [5.5, 1.0, 1.0, 1.0, 1.0]);
+        // still a valid file for the gltf crate, which drops the extension
+        assert_eq!(gltf::Gltf::from_slice(EMISSIVE.as_bytes()).unwrap().materials().count(), 5);
+        assert!(emissive_strengths(&serde_json::json!({ "asset": { "version": "2.0" } })).is_empty());
+    }
+}
@@ -0,0 +1,518 @@
+use wgpu::*;
+use bytemuck::{Pod, Zeroable};
+use gltf::{Primitive, Semantic};
+use gltf::buffer::Data;
+use anyhow::{Result, anyhow};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+use std::cell::RefCell;
+use crate::strategy::BindGroupStrategy;
+use crate::material::GpuMaterial;
+use crate::arena::{GeometryArena, GeometryArenaBuilder, MeshBuffers};
+use crate::culling::{Frustum, transform_bounds, union_bounds};
+
+// what rigid meshes draw with. normals and tangents are unit length, so they're stored as snorm16 with w unused for the normal
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct StaticVertex {
+    position: [f32; 3],
+    normal: [i16; 4],
+    tex_coords: [f32; 2],
+    tangent: [i16; 4],
+}
+
+// a static vertex plus its joints and unorm16 weights, with u8 joints for skins of up to 256 joints
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct SkinnedVertex8 {
+    vertex: StaticVertex,
+    joints: [u8; 4],
+    weights: [u16; 4],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct SkinnedVertex16 {
+    vertex: StaticVertex,
+    joints: [u16; 4],
+    weights: [u16; 4],
+}
+
+// eight influences, for primitives with a second JOINTS_1 and WEIGHTS_1 set
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct SkinnedVertex16x8 {
+    vertex: StaticVertex,
+    joints: [u16; 8],
+    weights: [u16; 8],
+}
+
+const STATIC_ATTRIBUTES: [VertexAttribute; 4] = vertex_attr_array![0 => Float32x3, 1 => Snorm16x4, 2 => Float32x2, 3 => Snorm16x4];
+const SKINNED_8_ATTRIBUTES: [VertexAttribute; 6] = vertex_attr_array![0 => Float32x3, 1 => Snorm16x4, 2 => Float32x2, 3 => Snorm16x4, 4 => Uint8x4, 5 => Unorm16x4];
+const SKINNED_16_ATTRIBUTES: [VertexAttribute; 6] = vertex_attr_array![0 => Float32x3, 1 => Snorm16x4, 2 => Float32x2, 3 => Snorm16x4, 4 => Uint16x4, 5 => Unorm16x4];
+// the first four influences are at the same locations as with four, the other four come after
+const SKINNED_16X8_ATTRIBUTES: [VertexAttribute; 8] = vertex_attr_array![0 => Float32x3, 1 => Snorm16x4, 2 => Float32x2, 3 => Snorm16x4, 4 => Uint16x4, 6 => Uint16x4, 5 => Unorm16x4, 7 => Unorm16x4];
+
+// which vertex format a primitive's vertices are in. each has its own vertex buffer in the arena and its own pipelines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VertexKind {
+    Static,
+    Skinned8,
+    Skinned16,
+    Skinned16x8,
+}
+
+impl VertexKind {
+    pub const ALL: [VertexKind; 4] = [VertexKind::Static, VertexKind::Skinned8, VertexKind::Skinned16, VertexKind::Skinned16x8];
+
+    // skin_joints is how many joints the skin of the node drawing the primitive has, if it has one.
+    // primitives without joints are drawn rigidly even on skinned nodes. max_influences is the mesh's cap, from its
+    // extras.maxInfluences or the load options: primitives with a second set of joints and weights get eight
+    // influences unless it's 4 or less, and ones with a single set always get four
+    pub fn new(primitive: &Primitive, skin_joints: Option<usize>, max_influences: Option<usize>) -> Self {
+        let count = |set: fn(u32) -> Semantic| (0..).take_while(|&i| primitive.get(&set(i)).is_some()).count();
+        let sets = count(Semantic::Joints).min(count(Semantic::Weights));
+        match skin_joints {
+            Some(_) if primitive.get(&Semantic::Joints(0)).is_none() => VertexKind::Static,
+            Some(_) if sets > 1 && max_influences.is_none_or(|n| n > 4) => VertexKind::Skinned16x8,
+            Some(joints) if joints <= 256 => VertexKind::Skinned8,
+            Some(_) => VertexKind::Skinned16,
+            None => VertexKind::Static,
+        }
+    }
+
+    // joints per vertex
+    pub fn influences(self) -> usize {
+        match self {
+            VertexKind::Static => 0,
+            VertexKind::Skinned8 | VertexKind::Skinned16 => 4,
+            VertexKind::Skinned16x8 => 8,
+        }
+    }
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn size(self) -> usize {
+        match self {
+            VertexKind::Static => std::mem::size_of::<StaticVertex>(),
+            VertexKind::Skinned8 => std::mem::size_of::<SkinnedVertex8>(),
+            VertexKind::Skinned16 => std::mem::size_of::<SkinnedVertex16>(),
+            VertexKind::Skinned16x8 => std::mem::size_of::<SkinnedVertex16x8>(),
+        }
+    }
+
+    pub fn desc(self) -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: self.size() as BufferAddress,
+            step_mode: VertexStepMode::default(),
+            attributes: match self {
+                VertexKind::Static => &STATIC_ATTRIBUTES,
+                VertexKind::Skinned8 => &SKINNED_8_ATTRIBUTES,
+                VertexKind::Skinned16 => &SKINNED_16_ATTRIBUTES,
+                VertexKind::Skinned16x8 => &SKINNED_16X8_ATTRIBUTES,
+            },
+        }
+    }
+
+    // the vertex shader entry point for this format, in every shader that draws meshes
+    pub fn entry_point(self) -> &'static str {
+        match self {
+            VertexKind::Static => "vs_static",
+            VertexKind::Skinned8 | VertexKind::Skinned16 => "vs_skinned",
+            VertexKind::Skinned16x8 => "vs_skinned_x8",
+        }
+    }
+}
+
+// a morph target's offsets for one vertex, w unused
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct MorphDelta {
+    position: [f32; 4],
+    normal: [f32; 4],
+}
+
+// where one glTF primitive is in the scene's GeometryArena, shared by every mesh that draws it
+#[derive(Debug, Clone)]
+pub struct Geometry {
+    pub kind: VertexKind,
+    pub first_index: u32,
+    pub length: u32,
+    // local space bounding box, min then max
+    pub bounds: [Vec3; 2],
+    pub first_vertex: u32,
+    pub vertex_count: u32,
+    pub morph_targets: u32,
+    // where every target's deltas for every vertex start, target by target
+    pub first_delta: u32,
+    // the box around each target's position deltas
+    pub morph_bounds: Vec<[Vec3; 2]>,
+}
+
+impl Geometry {
+    // mesh is the primitive's, to say which one lost joint influences when there were more than kind has room for
+    pub fn from_gltf(arena: &mut GeometryArenaBuilder, mesh: &gltf::Mesh, primitive: &Primitive, buffers: &[Data], kind: VertexKind) -> Result<Self> {
+        let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+        let positions = reader.read_positions().ok_or(anyhow!("Couldn't get positions"))?;
+        let normals = reader.read_normals().ok_or(anyhow!("Couldn't get normals"))?;
+
+        let indices_buf = reader.read_indices().ok_or(anyhow!("Couldn't get indices"))?.into_u32().collect::<Vec<_>>();
+
+        let positions = positions.collect::<Vec<_>>();
+        let normals = normals.collect::<Vec<_>>();
+        let tex_coords = reader.read_tex_coords(0)
+            .map(|t| t.into_f32().collect::<Vec<_>>())
+            .unwrap_or_else(|| vec![[0.0, 0.0]; positions.len()]);
+        let tangents = match reader.read_tangents() {
+            Some(tangents) => tangents.collect::<Vec<_>>(),
+            None => generate_tangents(&positions, &normals, &tex_coords, &indices_buf),
+        };
+
+        let static_vertices = positions.iter().zip(&normals).zip(&tex_coords).zip(&tangents).map(|(((p, n), uv), t)| {
+            StaticVertex {
+                position: *p,
+                normal: quantize_snorm([n[0], n[1], n[2], 0.0]),
+                tex_coords: *uv,
+                tangent: quantize_snorm(*t),
+            }
+        });
+
+        // every set of joints and weights, JOINTS_0 and WEIGHTS_0 then JOINTS_1 and WEIGHTS_1 and so on
+        let mut influences = vec![Vec::new(); if kind == VertexKind::Static { 0 } else { positions.len() }];
+        for set in 0.. {
+            let (joints, weights) = match (reader.read_joints(set), reader.read_weights(set)) {
+                (Some(joints), Some(weights)) => (joints, weights),
+                _ => break,
+            };
+            for (vertex, (j, w)) in influences.iter_mut().zip(joints.into_u16().zip(weights.into_f32())) {
+                vertex.extend(j.into_iter().zip(w));
+            }
+        }
+        let dropped = influences.iter().filter(|i| i.iter().filter(|(_, w)| *w > 0.0).count() > kind.influences()).count();
+        if dropped > 0 {
+            eprintln!("Warning: {} vertices of mesh {} primitive {} have more than {} joint influences, the weakest were dropped", dropped, mesh.name().map_or_else(|| mesh.index().to_string(), str::to_string), primitive.index(), kind.influences());
+        }
+
+        // targets can leave out positions or normals, which then don't move
+        let vertex_count = positions.len();
+        let mut morph_targets = 0;
+        let mut raw_deltas = Vec::new();
+        let mut morph_bounds = Vec::new();
+        for (target_positions, target_normals, _) in reader.read_morph_targets() {
+            let target_positions = target_positions.into_iter().flatten().chain(std::iter::repeat([0.0; 3]));
+            let target_normals = target_normals.into_iter().flatten().chain(std::iter::repeat([0.0; 3]));
+            raw_deltas.extend(target_positions.zip(target_normals).take(vertex_count).map(|(p, n)| MorphDelta {
+                position: [p[0], p[1], p[2], 0.0],
+                normal: [n[0], n[1], n[2], 0.0],
+            }));
+            let target = raw_deltas[raw_deltas.len() - vertex_count..].iter().map(|d| Vec4::from(d.position).truncate());
+            morph_bounds.push(target.fold([Vec3::ZERO; 2], |[min, max], d| [min.min(d), max.max(d)]));
+            morph_targets += 1;
+        }
+        let range = match kind {
+            VertexKind::Static => arena.push(kind, &static_vertices.collect::<Vec<_>>(), &indices_buf, &raw_deltas),
+            VertexKind::Skinned8 => {
+                let vertices = static_vertices.zip(&influences).map(|(vertex, i)| {
+                    let (joints, weights) = strongest_influences::<4>(i);
+                    SkinnedVertex8 { vertex, joints: joints.map(|j| j as u8), weights }
+                }).collect::<Vec<_>>();
+                arena.push(kind, &vertices, &indices_buf, &raw_deltas)
+            },
+            VertexKind::Skinned16 => {
+                let vertices = static_vertices.zip(&influences).map(|(vertex, i)| {
+                    let (joints, weights) = strongest_influences::<4>(i);
+                    SkinnedVertex16 { vertex, joints, weights }
+                }).collect::<Vec<_>>();
+                arena.push(kind, &vertices, &indices_buf, &raw_deltas)
+            },
+            VertexKind::Skinned16x8 => {
+                let vertices = static_vertices.zip(&influences).map(|(vertex, i)| {
+                    let (joints, weights) = strongest_influences::<8>(i);
+                    SkinnedVertex16x8 { vertex, joints, weights }
+                }).collect::<Vec<_>>();
+                arena.push(kind, &vertices, &indices_buf, &raw_deltas)
+            },
+        };
+
+        let bounding_box = primitive.bounding_box();
+        let bounds = [Vec3::from(bounding_box.min), Vec3::from(bounding_box.max)];
+
+        Ok(Self {
+            kind,
+            first_index: range.first_index,
+            length: indices_buf.len() as u32,
+            bounds,
+            first_vertex: range.first_vertex,
+            vertex_count: vertex_count as u32,
+            morph_targets,
+            first_delta: range.first_delta,
+            morph_bounds,
+        })
+    }
+
+    // the primitive's range of the arena's index buffer
+    pub fn indices(&self) -> std::ops::Range<u32> {
+        self.first_index..self.first_index + self.length
+    }
+}
+
+// one copy of a mesh, placed relative to a node. local is the identity unless it comes from EXT_mesh_gpu_instancing
+#[derive(Debug, Clone, Copy)]
+pub struct Instance {
+    pub node: usize,
+    pub local: Mat4,
+}
+
+// a primitive drawn with one instanced draw call, for every node (and gpu instance) that uses it
+pub struct Mesh {
+    pub index: usize,
+    pub geometry: Geometry,
+    pub instances: Vec<Instance>,
+    pub mat_index: Option<usize>,
+    pub skin_index: Option<usize>,
+    // binds this mesh's blocks of the scene's MeshBuffers
+    pub bind_group: Option<BindGroup>,
+    // world matrix of every instance
+    pub matrices: RefCell<Vec<Mat4>>,
+    // box around the posed mesh in its own space, and around every instance in world space
+    pub local_bounds: RefCell<[Vec3; 2]>,
+    pub world_bounds: RefCell<[Vec3; 2]>,
+    // skinned (and morphed) in a compute pass and drawn from its output as static vertices
+    pub gpu_skinned: bool,
+}
+
+impl Mesh {
+    pub fn new(geometry: Geometry, index: usize, mat_index: Option<usize>, skin_index: Option<usize>, instances: Vec<Instance>) -> Self {
+        Self {
+            index,
+            local_bounds: RefCell::new(geometry.bounds),
+            world_bounds: RefCell::new(geometry.bounds),
+            geometry,
+            matrices: RefCell::new(vec![Mat4::IDENTITY; instances.len()]),
+            instances,
+            mat_index,
+            skin_index,
+            bind_group: None,
+            gpu_skinned: false,
+        }
+    }
+
+    // skins and morph weights are per node, so meshes with either only ever have instances of one node
+    pub fn node(&self) -> usize {
+        self.instances[0].node
+    }
+
+    pub fn instance_count(&self) -> u32 {
+        self.instances.len() as u32
+    }
+
+    // middle of the bounding box, averaged over every instance
+    pub fn world_center(&self) -> Vec3 {
+        let center = (self.geometry.bounds[0] + self.geometry.bounds[1]) * 0.5;
+        let matrices = self.matrices.borrow();
+        matrices.iter().map(|m| m.transform_point3(center)).fold(Vec3::ZERO, |a, b| a + b) / matrices.len().max(1) as f32
+    }
+
+    // model then normal matrix for every instance. world holds every node's world matrix, by node index
+    pub fn instance_matrices(&self, world: &[Mat4]) -> Vec<Mat4> {
+        let mut matrices = self.matrices.borrow_mut();
+        matrices.clear();
+        matrices.extend(self.instances.iter().map(|i| world[i.node] * i.local));
+        matrices.iter().flat_map(|&m| [m, m.inverse().transpose()]).collect()
+    }
+
+    // recomputes local_bounds and world_bounds from the instance matrices, the mesh's joint matrices and its morph weights
+    pub fn update_bounds(&self, joint_matrices: &[Mat4], weights: &[f32]) {
+        let mut local = self.geometry.bounds;
+        for (target, &weight) in self.geometry.morph_bounds.iter().zip(weights) {
+            let [a, b] = [target[0] * weight, target[1] * weight];
+            local = [local[0] + a.min(b), local[1] + a.max(b)];
+        }
+        // skinned vertices are a weighted average of where each joint puts them, so the boxes every joint moves the mesh's box to cover them
+        if self.skin_index.is_some() {
+            local = joint_matrices.iter().map(|&j| transform_bounds(local, j)).reduce(union_bounds).unwrap_or(local);
+        }
+        let world = self.matrices.borrow().iter().map(|&m| transform_bounds(local, m)).reduce(union_bounds).unwrap_or(local);
+        self.local_bounds.replace(local);
+        self.world_bounds.replace(world);
+    }
+
+    pub fn vertex_kind(&self) -> VertexKind {
+        self.geometry.kind
+    }
+
+    pub fn is_visible(&self, frustum: &Frustum) -> bool {
+        frustum.intersects(*self.world_bounds.borrow())
+    }
+
+    // the morph weights header then one weight per target, weights past the mesh's targets are ignored and missing ones are 0.
+    // gpu skinned meshes are already morphed when they're drawn, so their header has no targets, but the weights are still there for the skinning pass
+    pub fn morph_weights(&self, weights: &[f32]) -> Vec<u32> {
+        let geometry = &self.geometry;
+        let targets = if self.gpu_skinned { 0 } else { geometry.morph_targets };
+        let header = [targets, geometry.vertex_count, geometry.first_vertex, geometry.first_delta];
+        let weights = weights.iter().copied().chain(std::iter::repeat(0.0)).take(geometry.morph_targets.max(1) as usize);
+        header.into_iter().chain(weights.map(f32::to_bits)).collect()
+    }
+
+    // block is the mesh's index in the scene, which is also its block in every MeshBuffers buffer
+    pub fn bind(&mut self, device: &Device, layout: &BindGroupLayout, arena: &GeometryArena, buffers: &MeshBuffers, block: usize, material: &GpuMaterial) {
+        self.bind_group = Some(create_bind_group(device, layout, material, arena, buffers, block));
+    }
+
+    // bind group for this frame under strategies that don't reuse the one made at load
+    #[allow(clippy::too_many_arguments)]
+    pub fn frame_bind_group(&self, strategy: BindGroupStrategy, device: &Device, layout: &BindGroupLayout, material: &GpuMaterial, arena: &GeometryArena, buffers: &MeshBuffers, block: usize) -> Option<BindGroup> {
+        if strategy != BindGroupStrategy::Mesh {
+            return None;
+        }
+        Some(create_bind_group(device, layout, material, arena, buffers, block))
+    }
+}
+
+fn create_bind_group(device: &Device, layout: &BindGroupLayout, material: &GpuMaterial, arena: &GeometryArena, buffers: &MeshBuffers, block: usize) -> BindGroup {
+    // material textures are bindings 3 to 7, their samplers 8 to 12, morph targets 13 and 14, and the visible instances 15
+    let textures = material.textures();
+    let mut entries = vec![
+        BindGroupEntry {
+            binding: 0,
+            resource: buffers.transforms.binding(block),
+        },
+        BindGroupEntry {
+            binding: 1,
+            resource: material.buffer.as_entire_binding(),
+        },
+        BindGroupEntry {
+            binding: 2,
+            resource: buffers.joint_matrices.binding(block),
+        }
+    ];
+    entries.extend(textures.iter().enumerate().map(|(i, t)| BindGroupEntry {
+        binding: 3 + i as u32,
+        resource: BindingResource::TextureView(&t.view),
+    }));
+    entries.extend(textures.iter().enumerate().map(|(i, t)| BindGroupEntry {
+        binding: 3 + textures.len() as u32 + i as u32,
+        resource: BindingResource::Sampler(&t.sampler),
+    }));
+    entries.push(BindGroupEntry {
+        binding: 13,
+        resource: arena.morph_deltas.as_entire_binding(),
+    });
+    entries.push(BindGroupEntry {
+        binding: 14,
+        resource: buffers.morph_weights.binding(block),
+    });
+    entries.push(BindGroupEntry {
+        binding: 15,
+        resource: buffers.visible.binding(block),
+    });
+    device.create_bind_group(&BindGroupDescriptor {
+        layout,
+        entries: &entries,
+        label: Some("mesh bind group"),
+    })
+}
+
+fn quantize_snorm(v: [f32; 4]) -> [i16; 4] {
+    v.map(|x| (x.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16)
+}
+
+// the N joints with the most weight, with their weights renormalized and quantized. vertices without any influences
+// get the first joint with all the weight
+fn strongest_influences<const N: usize>(influences: &[(u16, f32)]) -> ([u16; N], [u16; N]) {
+    let mut strongest = influences.to_vec();
+    strongest.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let mut joints = [0; N];
+    let mut weights = [0.0; N];
+    for (i, &(joint, weight)) in strongest.iter().take(N).enumerate() {
+        joints[i] = joint;
+        weights[i] = weight;
+    }
+    (joints, quantize_weights(weights))
+}
+
+// weights as unorm16, still summing to exactly 1 after rounding. whatever rounding lost or gained goes on the largest
+fn quantize_weights<const N: usize>(weights: [f32; N]) -> [u16; N] {
+    let sum = weights.iter().map(|w| w.max(0.0)).sum::<f32>();
+    if sum <= 0.0 {
+        let mut first = [0; N];
+        first[0] = u16::MAX;
+        return first;
+    }
+    let mut quantized = weights.map(|w| (w.max(0.0) / sum * u16::MAX as f32).round() as i32);
+    let largest = (0..N).max_by_key(|&i| quantized[i]).unwrap_or(0);
+    quantized[largest] += u16::MAX as i32 - quantized.iter().sum::<i32>();
+    quantized.map(|w| w.clamp(0, u16::MAX as i32) as u16)
+}
+
+// per vertex tangents from the uv gradients of the triangles around it, for meshes exported without any.
+// w is the handedness of the bitangent, like glTF's TANGENT
+fn generate_tangents(positions: &[[f32; 3]], normals: &[[f32; 3]], tex_coords: &[[f32; 2]], indices: &[u32]) -> Vec<[f32; 4]> {
+    let mut tangents = vec![Vec3::ZERO; positions.len()];
+    let mut bitangents = vec![Vec3::ZERO; positions.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0] as usize, triangle[1] as usize, triangle[2] as usize];
+        let e1 = Vec3::from(positions[b]) - Vec3::from(positions[a]);
+        let e2 = Vec3::from(positions[c]) - Vec3::from(positions[a]);
+        let d1 = Vec2::from(tex_coords[b]) - Vec2::from(tex_coords[a]);
+        let d2 = Vec2::from(tex_coords[c]) - Vec2::from(tex_coords[a]);
+        let det = d1.x * d2.y - d2.x * d1.y;
+        if det.abs() < f32::EPSILON {
+            continue;
+        }
+        let tangent = (e1 * d2.y - e2 * d1.y) / det;
+        let bitangent = (e2 * d1.x - e1 * d2.x) / det;
+        for i in [a, b, c] {
+            tangents[i] += tangent;
+            bitangents[i] += bitangent;
+        }
+    }
+
+    normals.iter().zip(tangents).zip(bitangents).map(|((n, t), b)| {
+        let n = Vec3::from(*n);
+        // gram-schmidt against the normal, falling back to anything perpendicular
+        let mut tangent = (t - n * n.dot(t)).normalize_or_zero();
+        if tangent == Vec3::ZERO {
+            tangent = n.any_orthonormal_vector();
+        }
+        let w = if n.cross(tangent).dot(b) < 0.0 { -1.0 } else { 1.0 };
+        Vec4::from((tangent, w)).into()
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vertex_sizes_match_skinning_shader() {
+        // skin.wgsl reads and writes vertices as this many words
+        assert_eq!(VertexKind::ALL.map(|k| k.size() / 4), [9, 12, 13, 17]);
+    }
+
+    #[test]
+    fn quantized_weights_sum_to_one() {
+        for weights in [[1.0, 0.0, 0.0, 0.0], [0.3333, 0.3333, 0.3334, 0.0], [0.1, 0.2, 0.3, 0.4], [0.5, 0.5, 0.0, 0.0]] {
+            assert_eq!(quantize_weights(weights).iter().map(|&w| w as u32).sum::<u32>(), u16::MAX as u32);
+        }
+        // unnormalized weights are scaled back to 1, and missing ones go to the first joint
+        assert_eq!(quantize_weights([2.0, 2.0, 0.0, 0.0]), [32768, 32767, 0, 0]);
+        assert_eq!(quantize_weights([0.0; 4]), [u16::MAX, 0, 0, 0]);
+    }
+
+    #[test]
+    fn strongest_influences_are_kept() {
+        let influences = [(1, 0.05), (2, 0.3), (3, 0.1), (4, 0.2), (5, 0.05), (6, 0.3)];
+        let (joints, weights) = strongest_influences::<4>(&influences);
+        assert_eq!(joints, [2, 6, 4, 3]);
+        assert_eq!(weights.iter().map(|&w| w as u32).sum::<u32>(), u16::MAX as u32);
+        // room to spare is left at joint 0 with no weight
+        let (joints, weights) = strongest_influences::<8>(&influences);
+        assert_eq!(joints, [2, 6, 4, 3, 1, 5, 0, 0]);
+        assert_eq!(weights[6..], [0, 0]);
+        assert_eq!(strongest_influences::<4>(&[]), ([0; 4], [u16::MAX, 0, 0, 0]));
+    }
+}
@@ -0,0 +1,130 @@
+use crate::mesh::Mesh;
+use glam::{Mat4, Vec3};
+
+// everything animation decides about a frame, worked out on the cpu before any of it goes to the gpu.
+// SceneGraph::evaluate makes these
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenePose {
+    // every node's world matrix by node index, nodes outside the scene stay at the identity
+    pub world: Vec<Mat4>,
+    // every node's morph target weights by node index
+    pub weights: Vec<Vec<f32>>,
+    // each skin's joints' world matrices times their inverse bind matrices
+    pub joints: Vec<Vec<Mat4>>,
+}
+
+impl ScenePose {
+    // a skin's joint matrices for a mesh on the given node, in the mesh's space like the vertex shaders want them
+    pub fn joint_matrices(&self, skin: usize, node: usize) -> Vec<Mat4> {
+        let inverse_mesh = self.world[node].inverse();
+        self.joints[skin].iter().map(|j| inverse_mesh * *j).collect()
+    }
+
+    // the joint matrices a mesh's vertex shader skins with, just the identity if it isn't skinned
+    pub fn mesh_joints(&self, mesh: &Mesh) -> Vec<Mat4> {
+        mesh.skin_index.map_or_else(|| vec![Mat4::IDENTITY], |skin| self.joint_matrices(skin, mesh.node()))
+    }
+
+    pub fn world_position(&self, node: usize) -> Vec3 {
+        self.world[node].transform_point3(Vec3::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::{Animation, AnimationClip, NodeTransform, PlaybackMode, Pose, Transformation};
+    use crate::graph::SceneGraph;
+    use glam::Quat;
+    use gltf::animation::Interpolation;
+    use std::f32::consts::FRAC_PI_2;
+
+    // an arm along y, the shoulder at (1, 0, 0) and the hand 2 above it, plus a node for the skinned mesh
+    const ARM: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0, 2] }],
+        "nodes": [
+            { "name": "shoulder", "translation": [1, 0, 0], "children": [1] },
+            { "name": "hand", "translation": [0, 2, 0] },
+            { "name": "mesh" }
+        ]
+    }"#;
+
+    // with one skin, bound in the rest pose
+    fn arm() -> SceneGraph {
+        let document = gltf::Gltf::from_slice(ARM.as_bytes()).unwrap().document;
+        let mut graph = SceneGraph::new(&document, &[], &[(0, 2)], Vec::new());
+        let bind = graph.evaluate(&Pose::default());
+        graph.skins = vec![vec![(0, bind.world[0].inverse()), (1, bind.world[1].inverse())]];
+        graph
+    }
+
+    // swings the shoulder a quarter turn about z over a second, then holds
+    fn swing() -> AnimationClip {
+        let rotations = [Transformation::Rotate(Quat::IDENTITY), Transformation::Rotate(Quat::from_rotation_z(FRAC_PI_2))];
+        let mut clip = AnimationClip::new("swing".to_string(), vec![Animation::from_keyframes(0, Interpolation::Linear, &[0.0, 1.0], &rotations)]);
+        clip.mode = PlaybackMode::Once;
+        clip
+    }
+
+    // what the vertex shaders do with a vertex's joints and weights
+    fn skin(joint_matrices: &[Mat4], joints: [usize; 4], weights: [f32; 4], position: Vec3) -> Vec3 {
+        joints.iter().zip(weights).map(|(&j, w)| joint_matrices[j].transform_point3(position) * w).fold(Vec3::ZERO, |a, b| a + b)
+    }
+
+    fn close(a: Vec3, b: Vec3) -> bool {
+        (a - b).length() < 1e-4
+    }
+
+    #[test]
+    fn rest_pose_composes_hierarchy() {
+        let graph = arm();
+        let pose = graph.evaluate(&Pose::default());
+        assert!(close(pose.world_position(0), Vec3::new(1.0, 0.0, 0.0)));
+        assert!(close(pose.world_position(1), Vec3::new(1.0, 2.0, 0.0)));
+        for joint in pose.joint_matrices(0, 2) {
+            assert!(joint.abs_diff_eq(Mat4::IDENTITY, 1e-5));
+        }
+    }
+
+    #[test]
+    fn animated_parent_moves_children() {
+        let graph = arm();
+        let clip = swing();
+        let pose = graph.evaluate(&Pose::sample(&clip, 1.0, &graph.rest));
+        assert!(close(pose.world_position(0), Vec3::new(1.0, 0.0, 0.0)));
+        assert!(close(pose.world_position(1), Vec3::new(-1.0, 0.0, 0.0)));
+
+        // halfway through, the hand is an eighth of a turn round
+        let pose = graph.evaluate(&Pose::sample(&clip, 0.5, &graph.rest));
+        let offset = 2.0_f32.sqrt();
+        assert!(close(pose.world_position(1), Vec3::new(1.0 - offset, offset, 0.0)));
+    }
+
+    #[test]
+    fn skinning_follows_joints() {
+        let graph = arm();
+        let pose = graph.evaluate(&Pose::sample(&swing(), 1.0, &graph.rest));
+        let joint_matrices = pose.joint_matrices(0, 2);
+
+        // a vertex on the hand goes where the hand goes, one on the shoulder turns on the spot
+        let fingertip = Vec3::new(1.0, 3.0, 0.0);
+        assert!(close(skin(&joint_matrices, [1, 0, 0, 0], [1.0, 0.0, 0.0, 0.0], fingertip), Vec3::new(-2.0, 0.0, 0.0)));
+        let elbow = Vec3::new(1.0, 1.0, 0.0);
+        assert!(close(skin(&joint_matrices, [0, 0, 0, 0], [1.0, 0.0, 0.0, 0.0], elbow), Vec3::new(0.0, 0.0, 0.0)));
+        // split between both joints, which here turn it together
+        assert!(close(skin(&joint_matrices, [0, 1, 0, 0], [0.5, 0.5, 0.0, 0.0], elbow), Vec3::new(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn joint_matrices_are_relative_to_the_mesh() {
+        let graph = arm();
+        // moving the mesh node moves the skinned vertices' frame along with it
+        let moved = NodeTransform { translation: Vec3::new(0.0, 0.0, 5.0), ..graph.rest[2].clone() };
+        let pose = graph.evaluate(&Pose { nodes: [(2, moved)].into_iter().collect() });
+        let joint_matrices = pose.joint_matrices(0, 2);
+        let world = pose.world[2].transform_point3(skin(&joint_matrices, [1, 0, 0, 0], [1.0, 0.0, 0.0, 0.0], Vec3::new(1.0, 2.0, 0.0)));
+        assert!(close(world, Vec3::new(1.0, 2.0, 0.0)));
+    }
+}
@@ -0,0 +1,172 @@
+use wgpu::*;
+use serde::Serialize;
+use futures::executor::block_on;
+use std::cell::RefCell;
+use std::time::Instant;
+
+// every pass takes two timestamps, one on each side
+const MAX_PASSES: u32 = 64;
+const TIMESTAMP_SIZE: BufferAddress = std::mem::size_of::<u64>() as BufferAddress;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PassTiming {
+    pub name: String,
+    pub duration_ns: u64,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct FrameTimings {
+    // true if these came from gpu timestamps, false if they're only how long each pass took to record
+    pub gpu: bool,
+    pub passes: Vec<PassTiming>,
+}
+
+impl FrameTimings {
+    pub fn total_ns(&self) -> u64 {
+        self.passes.iter().map(|p| p.duration_ns).sum()
+    }
+}
+
+enum Timer {
+    Timestamps {
+        query_set: QuerySet,
+        readback_buffer: Buffer,
+        period: f32,
+    },
+    // used when the adapter doesn't support Features::TIMESTAMP_QUERY
+    Cpu,
+}
+
+#[derive(Default)]
+struct FrameState {
+    names: Vec<String>,
+    open: bool,
+    cpu_start: Option<Instant>,
+    cpu_durations: Vec<u64>,
+}
+
+pub struct Profiler {
+    timer: Timer,
+    frame: RefCell<FrameState>,
+    pub enabled: bool,
+}
+
+impl Profiler {
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        let timer = if device.features().contains(Features::TIMESTAMP_QUERY) {
+            let size = (MAX_PASSES * 2) as BufferAddress * TIMESTAMP_SIZE;
+            Timer::Timestamps {
+                query_set: device.create_query_set(&QuerySetDescriptor {
+                    label: Some("pass timestamps"),
+                    ty: QueryType::Timestamp,
+                    count: MAX_PASSES * 2,
+                }),
+                readback_buffer: device.create_buffer(&BufferDescriptor {
+                    label: Some("timestamp readback buffer"),
+                    size,
+                    usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                }),
+                period: queue.get_timestamp_period(),
+            }
+        } else {
+            Timer::Cpu
+        };
+
+        Self {
+            timer,
+            frame: RefCell::new(FrameState::default()),
+            enabled: false,
+        }
+    }
+
+    pub fn uses_timestamps(&self) -> bool {
+        matches!(self.timer, Timer::Timestamps { .. })
+    }
+
+    pub fn begin_frame(&self) {
+        *self.frame.borrow_mut() = FrameState::default();
+    }
+
+    pub fn begin_pass(&self, encoder: &mut CommandEncoder, name: impl Into<String>) {
+        if !self.enabled {
+            return;
+        }
+        let mut frame = self.frame.borrow_mut();
+        let index = frame.names.len() as u32;
+        if index >= MAX_PASSES {
+            return;
+        }
+        frame.names.push(name.into());
+        frame.open = true;
+        match &self.timer {
+            Timer::Timestamps { query_set, .. } => encoder.write_timestamp(query_set, index * 2),
+            Timer::Cpu => frame.cpu_start = Some(Instant::now()),
+        }
+    }
+
+    pub fn end_pass(&self, encoder: &mut CommandEncoder) {
+        if !self.enabled {
+            return;
+        }
+        let mut frame = self.frame.borrow_mut();
+        if !frame.open {
+            return;
+        }
+        frame.open = false;
+        let index = frame.names.len() as u32;
+        match &self.timer {
+            Timer::Timestamps { query_set, .. } => encoder.write_timestamp(query_set, index * 2 - 1),
+            Timer::Cpu => {
+                let elapsed = frame.cpu_start.take().map_or(0, |start| start.elapsed().as_nanos() as u64);
+                frame.cpu_durations.push(elapsed);
+            },
+        }
+    }
+
+    // resolves this frame's timestamps into a mappable buffer, has to be recorded before the encoder is finished
+    pub fn resolve(&self, encoder: &mut CommandEncoder) {
+        if !self.enabled {
+            return;
+        }
+        if let Timer::Timestamps { query_set, readback_buffer, .. } = &self.timer {
+            let count = self.frame.borrow().names.len() as u32 * 2;
+            if count == 0 {
+                return;
+            }
+            encoder.resolve_query_set(query_set, 0..count, readback_buffer, 0);
+        }
+    }
+
+    // timings for the last submitted frame, this waits on the gpu when timestamps are in use
+    pub fn read(&self, device: &Device) -> Option<FrameTimings> {
+        if !self.enabled {
+            return None;
+        }
+        let frame = self.frame.borrow();
+        if frame.names.is_empty() {
+            return None;
+        }
+        let durations = match &self.timer {
+            Timer::Timestamps { readback_buffer, period, .. } => {
+                let size = frame.names.len() as BufferAddress * 2 * TIMESTAMP_SIZE;
+                let slice = readback_buffer.slice(..size);
+                let mapping = slice.map_async(MapMode::Read);
+                device.poll(Maintain::Wait);
+                block_on(mapping).ok()?;
+                let durations = bytemuck::cast_slice::<u8, u64>(&slice.get_mapped_range())
+                    .chunks(2)
+                    .map(|t| (t[1].saturating_sub(t[0]) as f64 * *period as f64) as u64)
+                    .collect();
+                readback_buffer.unmap();
+                durations
+            },
+            Timer::Cpu => frame.cpu_durations.clone(),
+        };
+
+        Some(FrameTimings {
+            gpu: self.uses_timestamps(),
+            passes: frame.names.iter().cloned().zip(durations).map(|(name, duration_ns)| PassTiming { name, duration_ns }).collect(),
+        })
+    }
+}
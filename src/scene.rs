@@ -0,0 +1,472 @@
+use wgpu::*;
+use crate::mesh::{Geometry, Instance, Mesh, VertexKind};
+use crate::camera::{Camera, Projection};
+use crate::material::{Material, GpuMaterial, DefaultTextures, emissive_strengths};
+use crate::texture::ImageTexture;
+use crate::sky::Sky;
+use crate::light::{LightJSON, Light};
+use crate::animation::{AnimationClip, AnimationMixer, Pose};
+use crate::graph::SceneGraph;
+use crate::pose::ScenePose;
+use crate::arena::{GeometryArena, GeometryArenaBuilder, MeshBuffers};
+use anyhow::{Result, anyhow};
+use glam::{Mat4, Quat, Vec3};
+use gltf::{Node, buffer::Data, Document, Accessor};
+use gltf::accessor::{DataType, Iter};
+use gltf::material::AlphaMode;
+use std::f32::consts::PI;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+pub struct Scene {
+    pub camera: Camera,
+    pub sky: Sky,
+    pub meshes: Vec<Mesh>,
+    // every mesh's vertices and indices
+    pub arena: GeometryArena,
+    // every mesh's transforms, joints and morph weights
+    pub mesh_buffers: MeshBuffers,
+    pub lights: Vec<Light>,
+    pub materials: Vec<GpuMaterial>,
+    pub default_material: GpuMaterial,
+    pub clips: Vec<AnimationClip>,
+    pub mixer: AnimationMixer,
+    pub graph: SceneGraph,
+    pub source: Document,
+}
+
+// choices made while loading a scene, rather than read from it
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    // the light sidecar, which otherwise is the .json next to the scene if there is one
+    pub lights_path: Option<PathBuf>,
+    // at most 4 keeps every skinned vertex at four influences, even for primitives with a second set of joints.
+    // a mesh's extras.maxInfluences overrides it
+    pub max_influences: Option<usize>,
+}
+
+impl Scene {
+    // file_path can be a .glb, a .gltf (external or data uri buffers), or either without its extension
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_gltf(device: &Device, queue: &Queue, mat_layout: &BindGroupLayout, light_layout: &BindGroupLayout, texture_layout: &BindGroupLayout, file_path: impl AsRef<Path>, options: &LoadOptions) -> Result<Self> {
+
+        let gltf_path = find_gltf(file_path.as_ref())?;
+        let json_path = match &options.lights_path {
+            Some(path) => Some(path.clone()),
+            None => Some(gltf_path.with_extension("json")).filter(|p| p.is_file() && *p != gltf_path),
+        };
+
+        let (source, buffers, images) = gltf::import(&gltf_path).map_err(|e| anyhow!("Couldn't load {}: {}", gltf_path.display(), e))?;
+        // the sidecar is optional, and its lights override glTF lights on the node with the same name
+        let mut lights_raw = match json_path {
+            Some(path) => LightJSON::from_file(&path).map_err(|e| anyhow!("Couldn't load lights from {}: {}", path.display(), e))?,
+            None => Vec::new(),
+        };
+
+        // each texture is uploaded once per color space it's used in
+        let default_textures = DefaultTextures::new(device, queue);
+        let mut textures: HashMap<(usize, bool), Rc<ImageTexture>> = HashMap::new();
+        let mut texture = |t: &gltf::Texture, srgb: bool| {
+            textures.entry((t.index(), srgb))
+                .or_insert_with(|| Rc::new(ImageTexture::from_gltf(device, queue, t, &images, srgb)))
+                .clone()
+        };
+        let json = read_json(&gltf_path)?;
+        let strengths = emissive_strengths(&json);
+        let materials: Vec<GpuMaterial> = source.materials().map(|m| {
+            let strength = m.index().and_then(|i| strengths.get(i)).copied().unwrap_or(1.0);
+            GpuMaterial::from_gltf(&m, strength, device, &default_textures, &mut texture)
+        }).collect();
+
+        let clips = source.animations().map(|a| AnimationClip::from_gltf(&a, &buffers)).collect::<Vec<_>>();
+        let mixer = AnimationMixer::new(if clips.is_empty() { None } else { Some(0) });
+
+        // materials used in bunnyscene reference aren't actually ones in the gltf file, these are those
+        //let materials = vec![
+        //    Material::new(0.1, 1.0, 1.5, Vec3::new(0.2, 0.3, 0.8)).to_buffer(device),
+        //    Material::new(0.2, 1.0, 1.5, Vec3::new(0.2, 0.2, 0.2)).to_buffer(device),
+        //];
+
+        let mut mesh_nodes = Vec::new();
+        let mut maybe_camera = None;
+        let mut punctual_lights = Vec::new();
+
+        for node in source.default_scene().unwrap().nodes() {
+            parse_node(node, Mat4::IDENTITY, &mut mesh_nodes, device, &mut maybe_camera, &mut lights_raw, &mut punctual_lights);
+        }
+        let mut arena = GeometryArenaBuilder::default();
+        let mut meshes = instance_meshes(&mut arena, &source, &buffers, &mesh_nodes, &gpu_instances(&json, &source, &buffers)?, &max_influences(&json, options.max_influences))?;
+        let arena = arena.build(device);
+
+        let lights_raw = LightJSON::merge(lights_raw, punctual_lights);
+
+        let camera = maybe_camera.unwrap_or_else(|| Camera::new(device, Vec3::new(6.0, 8.0, 10.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Projection::Perspective { yfov: 0.5, aspect: 1.333, near: 0.1, far: 50.0 }));
+
+        let lights = lights_raw.iter().map(|light| light.to_light(device, light_layout, texture_layout)).collect::<Vec<_>>();
+
+        let sky = Sky::new(80.0 * PI / 180.0, 8.0, device);
+
+        let default_material = GpuMaterial::new(&Material::new(0.5, 1.0, 1.5, Vec3::new(0.5, 0.5, 0.5)), device, &default_textures);
+
+        let graph = SceneGraph::from_gltf(&source, &buffers, &clips, &mesh_instance_nodes(&meshes));
+        let rest_pose = graph.evaluate(&Pose::default());
+        let mesh_buffers = MeshBuffers::new(device, &meshes, &rest_pose, lights.len());
+        for (block, mesh) in meshes.iter_mut().enumerate() {
+            let material = mesh.mat_index.map_or(&default_material, |i| &materials[i]);
+            mesh.bind(device, mat_layout, &arena, &mesh_buffers, block, material);
+        }
+
+        Ok(Self {
+            meshes,
+            arena,
+            mesh_buffers,
+            camera,
+            lights,
+            sky,
+            clips,
+            mixer,
+            graph,
+            source,
+            materials,
+            default_material,
+        })
+    }
+
+    pub fn material(&self, mesh: &Mesh) -> &GpuMaterial {
+        mesh.mat_index.map_or(&self.default_material, |i| &self.materials[i])
+    }
+
+    pub fn clip_names(&self) -> impl Iterator<Item = &str> {
+        self.clips.iter().map(|c| c.name.as_str())
+    }
+
+    pub fn find_clip(&self, name: &str) -> Option<usize> {
+        self.clips.iter().position(|c| c.name == name)
+    }
+
+    fn check_clip(&self, index: Option<usize>) -> Result<()> {
+        match index.filter(|&i| i >= self.clips.len()) {
+            Some(i) => Err(anyhow!("There's no animation clip {}, the scene has {}", i, self.clips.len())),
+            None => Ok(()),
+        }
+    }
+
+    // cuts to a clip, None leaves every node in its rest pose. time is the time passed to animate
+    pub fn select_clip(&mut self, index: Option<usize>, time: f32) -> Result<()> {
+        self.check_clip(index)?;
+        self.mixer.play(index, time);
+        Ok(())
+    }
+
+    // fades from whatever is playing to a clip over duration seconds
+    pub fn crossfade_to(&mut self, index: Option<usize>, time: f32, duration: f32) -> Result<()> {
+        self.check_clip(index)?;
+        self.mixer.crossfade(index, time, duration);
+        Ok(())
+    }
+
+    pub fn clip(&self) -> Option<&AnimationClip> {
+        self.mixer.current().map(|i| &self.clips[i])
+    }
+
+    pub fn clip_mut(&mut self) -> Option<&mut AnimationClip> {
+        self.mixer.current().map(move |i| &mut self.clips[i])
+    }
+
+    pub fn pose(&self, time: f32) -> Pose {
+        self.mixer.pose(&self.clips, time, &self.graph.rest)
+    }
+
+    pub fn find_node(&self, name: &str) -> Option<usize> {
+        self.graph.find(name)
+    }
+
+    // where everything is at this time on the mixer's clock, without touching the gpu
+    pub fn evaluate_pose(&self, time: f32) -> ScenePose {
+        self.graph.evaluate(&self.pose(time))
+    }
+
+    pub fn upload_pose(&self, pose: &ScenePose, queue: &Queue) {
+        self.mesh_buffers.write(queue, &self.meshes, pose);
+    }
+
+    pub fn animate(&self, time: f32, queue: &Queue) {
+        self.upload_pose(&self.evaluate_pose(time), queue);
+    }
+}
+
+// the path itself if it's a glTF file, otherwise the first glTF file with the same stem.
+// gltf::import works out binary vs json from the contents, extensions only matter for finding the file
+fn find_gltf(path: &Path) -> Result<PathBuf> {
+    if path.is_file() && is_gltf(path) {
+        return Ok(path.to_path_buf());
+    }
+    ["glb", "gltf"].iter()
+        .map(|ext| path.with_extension(ext))
+        .find(|p| p.is_file() && is_gltf(p))
+        .ok_or_else(|| anyhow!("Couldn't find a glTF file at {}", path.display()))
+}
+
+// glb files start with the magic "glTF", json glTF is an object (light sidecars are arrays)
+fn is_gltf(path: &Path) -> bool {
+    let mut start = [0; 64];
+    let read = match std::fs::File::open(path).and_then(|mut f| f.read(&mut start)) {
+        Ok(read) => read,
+        Err(_) => return false,
+    };
+    let start = &start[..read];
+    start.starts_with(b"glTF") || start.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'{')
+}
+
+// the file's json, straight out of the glb chunk if it's binary. for extensions the gltf crate drops
+fn read_json(gltf_path: &Path) -> Result<serde_json::Value> {
+    let bytes = std::fs::read(gltf_path)?;
+    let json = if bytes.starts_with(b"glTF") {
+        gltf::Glb::from_slice(&bytes)?.json.into_owned()
+    } else {
+        bytes
+    };
+    Ok(serde_json::from_slice(&json)?)
+}
+
+// walks the node tree once at load for the nodes with meshes, the camera and lights.
+// everything that moves afterwards goes through the SceneGraph instead
+fn parse_node(node: Node, mut parent_mat: Mat4, mesh_nodes: &mut Vec<usize>, device: &Device, camera: &mut Option<Camera>, lights: &mut [LightJSON], punctual_lights: &mut Vec<LightJSON>) {
+    parent_mat *= Mat4::from_cols_array_2d(&node.transform().matrix());
+
+    if let Some(name) = node.name() {
+        if let Some(i) = lights.iter().position(|l| l.get_node() == name) {
+            lights[i].apply_matrix(parent_mat);
+        }
+    }
+    if let Some(light) = node.light() {
+        let name = node.name().map_or_else(|| format!("node {}", node.index()), String::from);
+        let mut light = LightJSON::from_gltf(&light, name);
+        light.apply_matrix(parent_mat);
+        punctual_lights.push(light);
+    }
+    
+    if camera.is_none() {
+        *camera = node.camera().map(|c| Camera::from_gltf(device, c.projection(), parent_mat));
+    }
+    if node.mesh().is_some() {
+        mesh_nodes.push(node.index());
+    }
+    for node in node.children() {
+        parse_node(node, parent_mat, mesh_nodes, device, camera, lights, punctual_lights);
+    }
+}
+
+// one Mesh per primitive that every static node using it shares, drawn instanced.
+// skins and morph weights are per node and blended meshes get sorted one at a time, so those nodes keep a Mesh each,
+// although their vertices and indices are still only put in the arena once per primitive and vertex format
+fn instance_meshes(arena: &mut GeometryArenaBuilder, document: &Document, buffers: &[Data], mesh_nodes: &[usize], gpu_instances: &HashMap<usize, Vec<Mat4>>, max_influences: &[Option<usize>]) -> Result<Vec<Mesh>> {
+    let nodes = document.nodes().collect::<Vec<_>>();
+    // by (mesh, primitive) and the format its vertices are in, which depends on the node's skin
+    let mut geometries: HashMap<((usize, usize), VertexKind), Geometry> = HashMap::new();
+    // (mesh, primitive) to the index of the Mesh static nodes are batched into
+    let mut batches: HashMap<(usize, usize), usize> = HashMap::new();
+    let mut meshes: Vec<Mesh> = Vec::new();
+
+    for &index in mesh_nodes {
+        let node = &nodes[index];
+        let mesh = node.mesh().ok_or_else(|| anyhow!("Node {} has no mesh", index))?;
+        let instances = gpu_instances.get(&index).map_or_else(|| vec![Mat4::IDENTITY], Vec::clone)
+            .into_iter()
+            .map(|local| Instance { node: index, local })
+            .collect::<Vec<_>>();
+
+        for primitive in mesh.primitives() {
+            let kind = VertexKind::new(&primitive, node.skin().map(|s| s.joints().count()), max_influences.get(mesh.index()).copied().flatten());
+            let key = (mesh.index(), primitive.index());
+            let geometry = match geometries.get(&(key, kind)) {
+                Some(geometry) => geometry.clone(),
+                None => geometries.entry((key, kind)).or_insert(Geometry::from_gltf(arena, &mesh, &primitive, buffers, kind)?).clone(),
+            };
+
+            let batchable = node.skin().is_none() && geometry.morph_targets == 0 && primitive.material().alpha_mode() != AlphaMode::Blend;
+            match batches.get(&key) {
+                Some(&batch) if batchable => meshes[batch].instances.extend(&instances),
+                _ => {
+                    if batchable {
+                        batches.insert(key, meshes.len());
+                    }
+                    meshes.push(Mesh::new(geometry, mesh.index(), primitive.material().index(), node.skin().map(|s| s.index()), instances.clone()));
+                }
+            }
+        }
+    }
+
+    Ok(meshes)
+}
+
+// (mesh, node) for every node each mesh has instances of
+fn mesh_instance_nodes(meshes: &[Mesh]) -> Vec<(usize, usize)> {
+    let mut pairs = meshes.iter().enumerate()
+        .flat_map(|(i, mesh)| mesh.instances.iter().map(move |instance| (i, instance.node)))
+        .collect::<Vec<_>>();
+    pairs.dedup();
+    pairs
+}
+
+// each mesh's extras.maxInfluences if it has a number there, otherwise the load option. exporters don't write the
+// extras, it's for asking for four influences on a mesh with more, which keeps the strongest four and renormalizes
+// them into the smaller vertices
+fn max_influences(root: &serde_json::Value, option: Option<usize>) -> Vec<Option<usize>> {
+    root["meshes"].as_array().map_or_else(Vec::new, |meshes| {
+        meshes.iter()
+            .map(|m| m["extras"]["maxInfluences"].as_u64().map(|n| n as usize).or(option))
+            .collect()
+    })
+}
+
+// EXT_mesh_gpu_instancing's transforms by node index, relative to the node. the gltf crate doesn't parse the extension,
+// so the accessors are found through the raw json
+fn gpu_instances(root: &serde_json::Value, document: &Document, buffers: &[Data]) -> Result<HashMap<usize, Vec<Mat4>>> {
+    let accessors = document.accessors().collect::<Vec<_>>();
+    let accessor = |attributes: &serde_json::Value, name: &str| -> Result<Option<&Accessor>> {
+        match attributes[name].as_u64() {
+            Some(i) => accessors.get(i as usize).map(Some).ok_or_else(|| anyhow!("EXT_mesh_gpu_instancing {} refers to a missing accessor {}", name, i)),
+            None => Ok(None),
+        }
+    };
+
+    let mut instances = HashMap::new();
+    for (node, json) in root["nodes"].as_array().into_iter().flatten().enumerate() {
+        let attributes = &json["extensions"]["EXT_mesh_gpu_instancing"]["attributes"];
+        if !attributes.is_object() {
+            continue;
+        }
+        let translations = accessor(attributes, "TRANSLATION")?.map(|a| read_vec3s(a, buffers)).transpose()?;
+        let rotations = accessor(attributes, "ROTATION")?.map(|a| read_rotations(a, buffers)).transpose()?;
+        let scales = accessor(attributes, "SCALE")?.map(|a| read_vec3s(a, buffers)).transpose()?;
+
+        let count = [translations.as_ref().map(Vec::len), rotations.as_ref().map(Vec::len), scales.as_ref().map(Vec::len)]
+            .into_iter().flatten().min().unwrap_or(0);
+        // nothing to read, so the node is drawn like any other
+        if count == 0 {
+            continue;
+        }
+        let matrices = (0..count).map(|i| Mat4::from_scale_rotation_translation(
+            scales.as_ref().map_or(Vec3::ONE, |s| s[i]),
+            rotations.as_ref().map_or(Quat::IDENTITY, |r| r[i]),
+            translations.as_ref().map_or(Vec3::ZERO, |t| t[i]),
+        )).collect();
+        instances.insert(node, matrices);
+    }
+    Ok(instances)
+}
+
+fn read_vec3s(accessor: &Accessor, buffers: &[Data]) -> Result<Vec<Vec3>> {
+    Iter::<[f32; 3]>::new(accessor.clone(), |b| buffers.get(b.index()).map(|d| &d[..]))
+        .map(|iter| iter.map(Vec3::from).collect())
+        .ok_or_else(|| anyhow!("Couldn't read accessor {}", accessor.index()))
+}
+
+// rotations can be floats or normalized signed integers
+fn read_rotations(accessor: &Accessor, buffers: &[Data]) -> Result<Vec<Quat>> {
+    let get = |b: gltf::Buffer| buffers.get(b.index()).map(|d| &d[..]);
+    let rotations = match accessor.data_type() {
+        DataType::F32 => Iter::<[f32; 4]>::new(accessor.clone(), get).map(|iter| iter.map(Quat::from_array).collect::<Vec<_>>()),
+        DataType::I16 => Iter::<[i16; 4]>::new(accessor.clone(), get).map(|iter| iter.map(|q| Quat::from_array(q.map(|c| (c as f32 / 32767.0).max(-1.0)))).collect()),
+        DataType::I8 => Iter::<[i8; 4]>::new(accessor.clone(), get).map(|iter| iter.map(|q| Quat::from_array(q.map(|c| (c as f32 / 127.0).max(-1.0)))).collect()),
+        other => return Err(anyhow!("EXT_mesh_gpu_instancing rotations can't be {:?}", other)),
+    };
+    rotations
+        .map(|r| r.into_iter().map(Quat::normalize).collect())
+        .ok_or_else(|| anyhow!("Couldn't read accessor {}", accessor.index()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // two instances on node 1: one moved along x, one moved up y and turned a quarter about z with normalized shorts
+    const INSTANCED: &str = r#"{
+        "asset": { "version": "2.0" },
+        "extensionsUsed": ["EXT_mesh_gpu_instancing"],
+        "buffers": [{ "byteLength": 40, "uri": "data:application/octet-stream;base64,AACAPwAAAAAAAAAAAAAAAAAAAEAAAAAAAAAAAAAA/38AAAAAglqCWg==" }],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 24 },
+            { "buffer": 0, "byteOffset": 24, "byteLength": 16 }
+        ],
+        "accessors": [
+            { "bufferView": 0, "componentType": 5126, "count": 2, "type": "VEC3" },
+            { "bufferView": 1, "componentType": 5122, "normalized": true, "count": 2, "type": "VEC4" }
+        ],
+        "nodes": [
+            {},
+            { "extensions": { "EXT_mesh_gpu_instancing": { "attributes": { "TRANSLATION": 0, "ROTATION": 1 } } } }
+        ]
+    }"#;
+
+    #[test]
+    fn sniffs_gltf_from_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, contents: &[u8]| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, contents).unwrap();
+            path
+        };
+        assert!(is_gltf(&write("binary.glb", b"glTF\x02\0\0\0")));
+        assert!(is_gltf(&write("text.gltf", b"\n  { \"asset\": { \"version\": \"2.0\" } }")));
+        // the extension doesn't matter either way
+        assert!(is_gltf(&write("binary.bin", b"glTF\x02\0\0\0")));
+        assert!(!is_gltf(&write("lights.json", b"[{ \"node\": \"Light\" }]")));
+        assert!(!is_gltf(&write("empty.gltf", b"")));
+        assert!(!is_gltf(&dir.path().join("missing.glb")));
+    }
+
+    #[test]
+    fn finds_gltf_by_path_or_stem() {
+        let dir = tempfile::tempdir().unwrap();
+        let glb = dir.path().join("binary.glb");
+        let gltf = dir.path().join("text.gltf");
+        std::fs::write(&glb, b"glTF\x02\0\0\0").unwrap();
+        std::fs::write(&gltf, b"{}").unwrap();
+        assert_eq!(find_gltf(&glb).unwrap(), glb);
+        assert_eq!(find_gltf(&gltf).unwrap(), gltf);
+        assert_eq!(find_gltf(&dir.path().join("binary")).unwrap(), glb);
+        assert_eq!(find_gltf(&dir.path().join("text")).unwrap(), gltf);
+
+        // a light sidecar with the scene's stem isn't the scene, but the scene next to it is
+        let sidecar = dir.path().join("binary.json");
+        std::fs::write(&sidecar, b"[]").unwrap();
+        assert_eq!(find_gltf(&sidecar).unwrap(), glb);
+        let lonely = dir.path().join("lonely.json");
+        std::fs::write(&lonely, b"[]").unwrap();
+        assert!(find_gltf(&lonely).is_err());
+        assert!(find_gltf(&dir.path().join("lonely")).is_err());
+    }
+
+    #[test]
+    fn reads_max_influences() {
+        let root = serde_json::json!({ "meshes": [
+            { "extras": { "maxInfluences": 4 } },
+            {},
+            { "extras": { "maxInfluences": "four" } },
+            { "extras": { "maxInfluences": 8 } },
+        ] });
+        assert_eq!(max_influences(&root, None), [Some(4), None, None, Some(8)]);
+        // the load option fills in for meshes without their own
+        assert_eq!(max_influences(&root, Some(4)), [Some(4), Some(4), Some(4), Some(8)]);
+        assert!(max_influences(&serde_json::json!({}), Some(4)).is_empty());
+    }
+
+    #[test]
+    fn reads_gpu_instances() {
+        let (document, buffers, _) = gltf::import_slice(INSTANCED.as_bytes()).unwrap();
+        let json = serde_json::from_str(INSTANCED).unwrap();
+        let instances = gpu_instances(&json, &document, &buffers).unwrap();
+        assert!(!instances.contains_key(&0));
+
+        let matrices = &instances[&1];
+        assert_eq!(matrices.len(), 2);
+        assert!(matrices[0].abs_diff_eq(Mat4::from_translation(Vec3::X), 1e-5));
+        let turned = Mat4::from_rotation_translation(Quat::from_rotation_z(PI / 2.0), Vec3::new(0.0, 2.0, 0.0));
+        assert!(matrices[1].abs_diff_eq(turned, 1e-4));
+    }
+}
@@ -43,6 +43,8 @@ struct InvCamera {
     inv_proj: mat4x4<f32>;
     inv_view: mat4x4<f32>;
     position: vec3<f32>;
+    // the way the camera looks, w is 1 when it's orthographic and every view ray points this way
+    forward: vec4<f32>;
 };
 
 [[group(1), binding(0)]]
@@ -86,6 +88,11 @@ let phi_sun: f32 = PI;
 [[group(5), binding(0)]]
 var<uniform> sky: Sky;
 
+[[group(6), binding(0)]]
+var material_texture: texture_2d<f32>;
+[[group(6), binding(1)]]
+var material_sampler: sampler;
+
 fn random(co: vec2<f32>) -> f32 {
     return fract(sin(dot(co.xy,vec2<f32>(12.9898,78.233))) * 43758.5453);
 }
@@ -213,13 +220,24 @@ fn sunsky_radiance(dir: vec3<f32>) -> vec3<f32> {
     return sun_radiance(dir) + sky_radiance(dir);
 }
 
+// from the eye to a point, which under an orthographic projection is the camera's forward axis wherever the point is
+fn view_ray(position: vec3<f32>) -> vec3<f32> {
+    if (inv_camera.forward.w > 0.0) {
+        return inv_camera.forward.xyz;
+    }
+    return normalize(position - inv_camera.position);
+}
+
 [[stage(fragment)]]
 fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
     let diffuse = textureSample(diffuse_texture, diffuse_sampler, in.tex_coords).xyz;
-    let normal = textureSample(normal_texture, normal_sampler, in.tex_coords).xyz;
+    let normal_occlusion = textureSample(normal_texture, normal_sampler, in.tex_coords);
+    let normal = normal_occlusion.xyz;
+    // metals have no diffuse term to light
+    let metallic = textureSample(material_texture, material_sampler, in.tex_coords).w;
     let position = get_world_position(in.tex_coords);
     if (normal.x == 0.0 && normal.y == 0.0 && normal.z == 0.0) {
-        let result = max(sunsky_radiance(normalize(position - inv_camera.position)), vec3<f32>(0.0, 0.0, 0.0));
+        let result = max(sunsky_radiance(view_ray(position)), vec3<f32>(0.0, 0.0, 0.0));
         return vec4<f32>(result, 1.0);
     }
     let TBN = make_tbn(normal);
@@ -237,6 +255,6 @@ fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
       i = i + 1.0;
     }
     let shadow_percent = total_shadowed / max_iter;
-    return vec4<f32>(light.radiance * diffuse * shadow_percent, 1.0);
+    return vec4<f32>(light.radiance * diffuse * (1.0 - metallic) * shadow_percent * normal_occlusion.w, 1.0);
 }
 
@@ -0,0 +1,179 @@
+// gpu culling: one invocation per mesh instance per culled pass. visible instances are appended to their mesh's list
+// for that pass, and counted into the instance count of the mesh's indirect draw
+
+struct Model {
+    model: mat4x4<f32>;
+    normal: mat4x4<f32>;
+};
+
+[[block]]
+struct Models {
+    models: array<Model>;
+};
+
+// bounds are the mesh's local box, after skinning and morph targets
+struct CullMesh {
+    bounds_min: vec4<f32>;
+    bounds_max: vec4<f32>;
+    // index of its first instance's matrices in the transforms buffer
+    first_model: u32;
+    // where its visible list starts in each pass's part of the visible buffer
+    first_visible: u32;
+    // where its draw is in each pass's draws
+    draw_slot: u32;
+    padding: u32;
+};
+
+[[block]]
+struct CullMeshes {
+    meshes: array<CullMesh>;
+};
+
+struct CullInstance {
+    mesh: u32;
+    instance: u32;
+};
+
+[[block]]
+struct CullInstances {
+    instances: array<CullInstance>;
+};
+
+// hiz_view_proj is the view projection the hi-z pyramid's depth was rendered with
+struct CullPass {
+    view_proj: mat4x4<f32>;
+    hiz_view_proj: mat4x4<f32>;
+    visible_base: u32;
+    occlusion: u32;
+    // where the pass's draws start in draws
+    draw_base: u32;
+    padding: u32;
+};
+
+[[block]]
+struct CullPasses {
+    passes: array<CullPass>;
+};
+
+struct DrawArgs {
+    index_count: u32;
+    instance_count: atomic<u32>;
+    first_index: u32;
+    base_vertex: i32;
+    first_instance: u32;
+};
+
+[[block]]
+struct Draws {
+    draws: array<DrawArgs>;
+};
+
+[[block]]
+struct Visible {
+    indices: array<u32>;
+};
+
+[[group(0), binding(0)]]
+var<storage, read> models: Models;
+
+[[group(0), binding(1)]]
+var<storage, read> meshes: CullMeshes;
+
+[[group(0), binding(2)]]
+var<storage, read> instances: CullInstances;
+
+[[group(0), binding(3)]]
+var<storage, read> passes: CullPasses;
+
+[[group(0), binding(4)]]
+var<storage, read_write> draws: Draws;
+
+[[group(0), binding(5)]]
+var<storage, read_write> visible: Visible;
+
+[[group(0), binding(6)]]
+var hiz: texture_2d<f32>;
+
+fn corner(mesh: CullMesh, i: u32) -> vec4<f32> {
+    return vec4<f32>(
+        select(mesh.bounds_min.x, mesh.bounds_max.x, (i & 1u) != 0u),
+        select(mesh.bounds_min.y, mesh.bounds_max.y, (i & 2u) != 0u),
+        select(mesh.bounds_min.z, mesh.bounds_max.z, (i & 4u) != 0u),
+        1.0,
+    );
+}
+
+// whether the box is entirely outside one of the clip volume's planes
+fn outside_frustum(mesh: CullMesh, mvp: mat4x4<f32>) -> bool {
+    var left = true;
+    var right = true;
+    var bottom = true;
+    var top = true;
+    var near = true;
+    var far = true;
+    for (var i: u32 = 0u; i < 8u; i = i + 1u) {
+        let c = mvp * corner(mesh, i);
+        left = left && c.x < -c.w;
+        right = right && c.x > c.w;
+        bottom = bottom && c.y < -c.w;
+        top = top && c.y > c.w;
+        near = near && c.z < 0.0;
+        far = far && c.z > c.w;
+    }
+    return left || right || bottom || top || near || far;
+}
+
+// whether the box is behind everything in the hi-z pyramid over the rectangle it covers
+fn occluded(mesh: CullMesh, mvp: mat4x4<f32>) -> bool {
+    var ndc_min = vec3<f32>(1.0, 1.0, 1.0);
+    var ndc_max = vec3<f32>(-1.0, -1.0, 0.0);
+    for (var i: u32 = 0u; i < 8u; i = i + 1u) {
+        let c = mvp * corner(mesh, i);
+        // crossing the camera plane, so it covers too much of the screen to bother
+        if (c.w <= 0.0) {
+            return false;
+        }
+        let ndc = c.xyz / c.w;
+        ndc_min = min(ndc_min, ndc);
+        ndc_max = max(ndc_max, ndc);
+    }
+    let uv_min = clamp(vec2<f32>(ndc_min.x, -ndc_max.y) * 0.5 + 0.5, vec2<f32>(0.0), vec2<f32>(1.0));
+    let uv_max = clamp(vec2<f32>(ndc_max.x, -ndc_min.y) * 0.5 + 0.5, vec2<f32>(0.0), vec2<f32>(1.0));
+
+    // the level where the rectangle is at most a texel across, so it touches at most 2x2 texels
+    let base_size = vec2<f32>(textureDimensions(hiz, 0));
+    let extent = (uv_max - uv_min) * base_size;
+    let level = clamp(i32(ceil(log2(max(max(extent.x, extent.y), 1.0)))), 0, textureNumLevels(hiz) - 1);
+    // levels halve rounding down, with the last row and column taking in the odd texels, so the texel over a pixel is
+    // its level 0 texel shifted down a level at a time, clamped into the level
+    let size = textureDimensions(hiz, level);
+    let texel_min = min(vec2<i32>(uv_min * base_size) >> vec2<u32>(u32(level)), size - 1);
+    let texel_max = min(vec2<i32>(uv_max * base_size) >> vec2<u32>(u32(level)), size - 1);
+    let furthest = max(
+        max(textureLoad(hiz, texel_min, level).x, textureLoad(hiz, vec2<i32>(texel_max.x, texel_min.y), level).x),
+        max(textureLoad(hiz, vec2<i32>(texel_min.x, texel_max.y), level).x, textureLoad(hiz, texel_max, level).x),
+    );
+    return ndc_min.z > furthest;
+}
+
+// x is the instance, y the culled pass
+[[stage(compute), workgroup_size(64)]]
+fn main([[builtin(global_invocation_id)]] id: vec3<u32>) {
+    if (id.x >= arrayLength(&instances.instances)) {
+        return;
+    }
+    let item = instances.instances[id.x];
+    let mesh = meshes.meshes[item.mesh];
+    let pass = passes.passes[id.y];
+    let model = models.models[mesh.first_model + item.instance].model;
+
+    if (outside_frustum(mesh, pass.view_proj * model)) {
+        return;
+    }
+    if (pass.occlusion != 0u && occluded(mesh, pass.hiz_view_proj * model)) {
+        return;
+    }
+    let draw = pass.draw_base + mesh.draw_slot;
+    let slot = atomicAdd(&draws.draws[draw].instance_count, 1u);
+    visible.indices[pass.visible_base + mesh.first_visible + slot] = item.instance;
+}
@@ -0,0 +1,481 @@
+// forward shading for alpha blended meshes, which can't go through the g-buffer.
+// each mesh is drawn once with fs_coverage to let through only (1 - alpha) of what's behind it
+// and add its emission, then once per light with that light's contribution added on top, scaled by alpha
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] world_normal: vec3<f32>;
+    [[location(1)]] world_tangent: vec4<f32>;
+    [[location(2)]] tex_coords: vec2<f32>;
+    [[location(3)]] world_position: vec3<f32>;
+};
+
+[[block]]
+struct Camera {
+    proj: mat4x4<f32>;
+    view: mat4x4<f32>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> cam_mats: Camera;
+
+struct Model {
+    model: mat4x4<f32>;
+    normal: mat4x4<f32>;
+};
+
+// one per instance
+[[block]]
+struct Instances {
+    models: array<Model>;
+};
+
+[[group(1), binding(0)]]
+var<storage, read> instances: Instances;
+
+// which instances this draw covers, after culling
+[[block]]
+struct VisibleInstances {
+    indices: array<u32>;
+};
+
+[[group(1), binding(15)]]
+var<storage, read> visible: VisibleInstances;
+
+[[block]]
+struct MatArray {
+    mats: array<mat4x4<f32>>;
+};
+
+[[group(1), binding(2)]]
+var<storage, read> joint_mats: MatArray;
+
+struct MorphDelta {
+    position: vec4<f32>;
+    normal: vec4<f32>;
+};
+
+[[block]]
+struct MorphDeltas {
+    deltas: array<MorphDelta>;
+};
+
+[[block]]
+struct MorphWeights {
+    target_count: u32;
+    vertex_count: u32;
+    // where the mesh's vertices and deltas start in the scene's shared buffers
+    first_vertex: u32;
+    first_delta: u32;
+    weights: array<f32>;
+};
+
+[[group(1), binding(13)]]
+var<storage, read> morph_deltas: MorphDeltas;
+
+[[group(1), binding(14)]]
+var<storage, read> morph_weights: MorphWeights;
+
+// morph targets move vertices in the mesh's own space, before skinning
+fn morph_position(position: vec3<f32>, vertex: u32) -> vec3<f32> {
+    var morphed = position;
+    for (var i: u32 = 0u; i < morph_weights.target_count; i = i + 1u) {
+        morphed = morphed + morph_weights.weights[i] * morph_deltas.deltas[morph_weights.first_delta + i * morph_weights.vertex_count + vertex - morph_weights.first_vertex].position.xyz;
+    }
+    return morphed;
+}
+
+fn morph_normal(normal: vec3<f32>, vertex: u32) -> vec3<f32> {
+    var morphed = normal;
+    for (var i: u32 = 0u; i < morph_weights.target_count; i = i + 1u) {
+        morphed = morphed + morph_weights.weights[i] * morph_deltas.deltas[morph_weights.first_delta + i * morph_weights.vertex_count + vertex - morph_weights.first_vertex].normal.xyz;
+    }
+    return morphed;
+}
+
+fn add_mats(m0: mat4x4<f32>, m1: mat4x4<f32>) -> mat4x4<f32> {
+    return mat4x4<f32>(m0.x + m1.x, m0.y + m1.y, m0.z + m1.z, m0.w + m1.w);
+}
+
+fn mul_scalar_mat(scalar: f32, mat: mat4x4<f32>) -> mat4x4<f32> {
+    return mat4x4<f32>(mat.x * scalar, mat.y * scalar, mat.z * scalar, mat.w * scalar);
+}
+
+fn mat4tomat3(m: mat4x4<f32>) -> mat3x3<f32> {
+    return mat3x3<f32>(m.x.xyz, m.y.xyz, m.z.xyz);
+}
+
+// position, normal and tangent are in the mesh's space, after morphing and skinning
+fn vertex_output(model_mats: Model, position: vec3<f32>, normal: vec3<f32>, tangent: vec4<f32>, tex_coords: vec2<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.world_normal = normalize((model_mats.normal * vec4<f32>(normal, 0.0)).xyz);
+    // tangents follow the surface, so they use the model matrix rather than the normal matrix
+    out.world_tangent = vec4<f32>(normalize((model_mats.model * vec4<f32>(tangent.xyz, 0.0)).xyz), tangent.w);
+    out.tex_coords = tex_coords;
+    let world_position = model_mats.model * vec4<f32>(position, 1.0);
+    out.world_position = world_position.xyz;
+    out.position = cam_mats.proj * cam_mats.view * world_position;
+    return out;
+}
+
+// the blend of the vertex's joints, its weights sum to 1
+fn skin_mat(joints: vec4<u32>, weights: vec4<f32>) -> mat4x4<f32> {
+    return add_mats(
+      add_mats(
+          mul_scalar_mat(weights.x, joint_mats.mats[joints.x]),
+          mul_scalar_mat(weights.y, joint_mats.mats[joints.y]),
+      ),
+      add_mats(
+          mul_scalar_mat(weights.z, joint_mats.mats[joints.z]),
+          mul_scalar_mat(weights.w, joint_mats.mats[joints.w])
+      )
+    );
+}
+
+[[stage(vertex)]]
+fn vs_static(
+    [[builtin(vertex_index)]] vertex_index: u32,
+    [[builtin(instance_index)]] instance_index: u32,
+    [[location(0)]] position: vec3<f32>,
+    [[location(1)]] normal: vec4<f32>,
+    [[location(2)]] tex_coords: vec2<f32>,
+    [[location(3)]] tangent: vec4<f32>,
+) -> VertexOutput {
+    let instance = visible.indices[instance_index];
+    return vertex_output(instances.models[instance], morph_position(position, vertex_index), morph_normal(normal.xyz, vertex_index), tangent, tex_coords);
+}
+
+[[stage(vertex)]]
+fn vs_skinned(
+    [[builtin(vertex_index)]] vertex_index: u32,
+    [[builtin(instance_index)]] instance_index: u32,
+    [[location(0)]] position: vec3<f32>,
+    [[location(1)]] normal: vec4<f32>,
+    [[location(2)]] tex_coords: vec2<f32>,
+    [[location(3)]] tangent: vec4<f32>,
+    [[location(4)]] joints: vec4<u32>,
+    [[location(5)]] weights: vec4<f32>,
+) -> VertexOutput {
+    let instance = visible.indices[instance_index];
+    let bones_mat = skin_mat(joints, weights);
+    let skinned_position = (bones_mat * vec4<f32>(morph_position(position, vertex_index), 1.0)).xyz;
+    let skinned_normal = mat4tomat3(bones_mat) * morph_normal(normal.xyz, vertex_index);
+    let skinned_tangent = vec4<f32>(mat4tomat3(bones_mat) * tangent.xyz, tangent.w);
+    return vertex_output(instances.models[instance], skinned_position, skinned_normal, skinned_tangent, tex_coords);
+}
+
+// eight influences, the second four from JOINTS_1 and WEIGHTS_1
+[[stage(vertex)]]
+fn vs_skinned_x8(
+    [[builtin(vertex_index)]] vertex_index: u32,
+    [[builtin(instance_index)]] instance_index: u32,
+    [[location(0)]] position: vec3<f32>,
+    [[location(1)]] normal: vec4<f32>,
+    [[location(2)]] tex_coords: vec2<f32>,
+    [[location(3)]] tangent: vec4<f32>,
+    [[location(4)]] joints: vec4<u32>,
+    [[location(5)]] weights: vec4<f32>,
+    [[location(6)]] joints2: vec4<u32>,
+    [[location(7)]] weights2: vec4<f32>,
+) -> VertexOutput {
+    let instance = visible.indices[instance_index];
+    let bones_mat = add_mats(skin_mat(joints, weights), skin_mat(joints2, weights2));
+    let skinned_position = (bones_mat * vec4<f32>(morph_position(position, vertex_index), 1.0)).xyz;
+    let skinned_normal = mat4tomat3(bones_mat) * morph_normal(normal.xyz, vertex_index);
+    let skinned_tangent = vec4<f32>(mat4tomat3(bones_mat) * tangent.xyz, tangent.w);
+    return vertex_output(instances.models[instance], skinned_position, skinned_normal, skinned_tangent, tex_coords);
+}
+
+let PI: f32 = 3.14159265358979323846264;
+
+[[block]]
+struct Camera_Pos {
+    inv_proj: mat4x4<f32>;
+    inv_view: mat4x4<f32>;
+    position: vec3<f32>;
+    // the way the camera looks, w is 1 when it's orthographic and every view ray points this way
+    forward: vec4<f32>;
+};
+
+[[group(0), binding(1)]]
+var<uniform> camera: Camera_Pos;
+
+// from a point towards the eye, which under an orthographic projection is back along the camera's forward axis
+// wherever the point is
+fn to_eye(position: vec3<f32>) -> vec3<f32> {
+    if (camera.forward.w > 0.0) {
+        return -camera.forward.xyz;
+    }
+    return normalize(camera.position - position);
+}
+
+[[block]]
+struct Material {
+    roughness: f32;
+    k_s: f32;
+    eta: f32;
+    diffuse: vec3<f32>;
+    normal_scale: f32;
+    occlusion_strength: f32;
+    metallic: f32;
+    opacity: f32;
+    alpha_cutoff: f32;
+    emissive: vec3<f32>;
+};
+
+[[group(1), binding(1)]]
+var<uniform> material: Material;
+
+[[group(1), binding(3)]]
+var base_color_texture: texture_2d<f32>;
+[[group(1), binding(4)]]
+var metallic_roughness_texture: texture_2d<f32>;
+[[group(1), binding(5)]]
+var normal_texture: texture_2d<f32>;
+[[group(1), binding(6)]]
+var occlusion_texture: texture_2d<f32>;
+[[group(1), binding(7)]]
+var emissive_texture: texture_2d<f32>;
+
+[[group(1), binding(8)]]
+var base_color_sampler: sampler;
+[[group(1), binding(9)]]
+var metallic_roughness_sampler: sampler;
+[[group(1), binding(10)]]
+var normal_sampler: sampler;
+[[group(1), binding(11)]]
+var occlusion_sampler: sampler;
+[[group(1), binding(12)]]
+var emissive_sampler: sampler;
+
+[[block]]
+struct Light {
+    proj: mat4x4<f32>;
+    view: mat4x4<f32>;
+    power: vec3<f32>;
+    // w is 0 for directional lights, which have irradiance in power instead
+    position: vec4<f32>;
+    direction: vec3<f32>;
+    // cos outer angle, cos inner angle, range (0 for none)
+    cone: vec3<f32>;
+};
+
+[[group(2), binding(0)]]
+var<uniform> light: Light;
+
+[[block]]
+struct AmbientLight {
+    radiance: vec3<f32>;
+    range: f32;
+};
+
+// only fs_ambient uses this, its pipeline has an ambient light here instead
+[[group(2), binding(0)]]
+var<uniform> ambient_light: AmbientLight;
+
+[[group(3), binding(0)]]
+var light_depth_texture: texture_depth_2d;
+[[group(3), binding(1)]]
+var light_depth_sampler: sampler_comparison;
+
+fn fresnel(i: vec3<f32>, m: vec3<f32>, eta: f32) -> f32 {
+    let c = abs(dot(i, m));
+    let g = sqrt(eta * eta - 1.0 + c * c);
+
+    let gmc = g - c;
+    let gpc = g + c;
+    let nom = c * (g + c) - 1.0;
+    let denom = c * (g - c) + 1.0;
+    return 0.5 * gmc * gmc / gpc / gpc * (1.0 + nom * nom / denom / denom);
+}
+
+// The one-sided Smith shadowing/masking function
+//   v -- in or out vector
+//   m -- microsurface normal
+//   n -- (macro) surface normal
+//   alpha -- surface roughness
+fn G1(v: vec3<f32>, m: vec3<f32>, n: vec3<f32>, alpha: f32) -> f32 {
+    let vm = dot(v, m);
+    let vn = dot(v, n);
+    var result: f32 = 0.0;
+    if (vm * vn > 0.0) {
+        let cosThetaV = dot(n, v);
+        let sinThetaV2 = 1.0 - cosThetaV * cosThetaV;
+        let tanThetaV2 = sinThetaV2 / cosThetaV / cosThetaV;
+        result = 2.0 / (1.0 + sqrt(1.0 + alpha * alpha * tanThetaV2));
+    }
+    return result;
+}
+
+// The GGX slope distribution function
+//   m -- microsurface normal
+//   n -- (macro) surface normal
+//   alpha -- surface roughness
+fn D(m: vec3<f32>, n: vec3<f32>, alpha: f32) -> f32 {
+    let mn = dot(m, n);
+    var result: f32 = 0.0;
+    if (mn > 0.0) {
+        let cosThetaM = mn;
+        let cosThetaM2 = cosThetaM * cosThetaM;
+        let tanThetaM2 = (1.0 - cosThetaM2) / cosThetaM2;
+        let cosThetaM4 =  cosThetaM * cosThetaM * cosThetaM * cosThetaM;
+        let X = (alpha * alpha + tanThetaM2);
+        result = alpha * alpha / (PI * cosThetaM4 * X * X);
+    }
+    return result;
+}
+
+// Evalutate the Microfacet BRDF (GGX variant) for the paramters:
+//   i -- incoming direction (unit vector, pointing away from surface)
+//   o -- outgoing direction (unit vector, pointing away from surface)
+//   n -- outward pointing surface normal vector
+//   eta -- refractive index
+//   alpha -- surface roughness
+// return: scalar BRDF value
+fn isotropic_microfacet(i: vec3<f32>, o: vec3<f32>, n: vec3<f32>, eta: f32, alpha: f32) -> f32 {
+    let odotn = dot(o, n);
+    let m = normalize(i + o);
+
+    let idotn = dot(i,n);
+    if (idotn <= 0.0 || odotn <= 0.0) {
+        return 0.0;
+    }
+
+    let idotm = dot(i, m);
+    var F: f32 = 0.0;
+    if (idotm > 0.0) {
+        F = fresnel(i,m,eta);
+    }
+    let G = G1(i, m, n, alpha) * G1(o, m, n, alpha);
+    return F * G * D(m, n, alpha) / (4.0 * idotn * odotn);
+}
+
+// The same microfacet BRDF for a conductor, with Schlick's Fresnel approximation
+// tinted by the base color instead of the dielectric Fresnel term
+//   f0 -- reflectance at normal incidence (the base color)
+// return: RGB BRDF value
+fn conductor_microfacet(i: vec3<f32>, o: vec3<f32>, n: vec3<f32>, f0: vec3<f32>, alpha: f32) -> vec3<f32> {
+    let odotn = dot(o, n);
+    let m = normalize(i + o);
+
+    let idotn = dot(i,n);
+    if (idotn <= 0.0 || odotn <= 0.0) {
+        return vec3<f32>(0.0, 0.0, 0.0);
+    }
+
+    let idotm = max(dot(i, m), 0.0);
+    let F = f0 + (vec3<f32>(1.0, 1.0, 1.0) - f0) * pow(1.0 - idotm, 5.0);
+    let G = G1(i, m, n, alpha) * G1(o, m, n, alpha);
+    return F * G * D(m, n, alpha) / (4.0 * idotn * odotn);
+}
+
+fn not_occluded(position: vec3<f32>) -> f32 {
+    // compute position in light space
+    let light_pos = light.proj * light.view * vec4<f32>(position, 1.0);
+
+    // vulkan's coordinate system is in [1, -1], [-1, 1], [0, 1] so we account for that
+    let flip = vec3<f32>(0.5, -0.5, 1.0);
+    let shadow_coords = light_pos.xyz * flip * (1.0 / light_pos.w) + vec3<f32>(0.5, 0.5, 0.0);
+
+    // get texture with comparison sampler
+    return textureSampleCompare(light_depth_texture, light_depth_sampler, shadow_coords.xy, shadow_coords.z);
+}
+
+// the same surface the geometry pass would write to the g-buffer
+struct Surface {
+    base_color: vec3<f32>;
+    opacity: f32;
+    normal: vec3<f32>;
+    alpha: f32;
+    metallic: f32;
+    occlusion: f32;
+    emissive: vec3<f32>;
+};
+
+fn surface(in: VertexOutput, front_facing: bool) -> Surface {
+    var out: Surface;
+    let base_color = textureSample(base_color_texture, base_color_sampler, in.tex_coords);
+    let metallic_roughness = textureSample(metallic_roughness_texture, metallic_roughness_sampler, in.tex_coords);
+    let occlusion = textureSample(occlusion_texture, occlusion_sampler, in.tex_coords).r;
+
+    let n = normalize(in.world_normal);
+    let t = normalize(in.world_tangent.xyz - n * dot(n, in.world_tangent.xyz));
+    let b = cross(n, t) * in.world_tangent.w;
+    let tangent_normal = (textureSample(normal_texture, normal_sampler, in.tex_coords).xyz * 2.0 - 1.0) * vec3<f32>(material.normal_scale, material.normal_scale, 1.0);
+    out.normal = normalize(mat3x3<f32>(t, b, n) * tangent_normal);
+    if (!front_facing) {
+      out.normal = -out.normal;
+    }
+
+    let roughness = material.roughness * metallic_roughness.g;
+    out.base_color = material.diffuse * base_color.rgb;
+    out.opacity = material.opacity * base_color.a;
+    out.alpha = max(roughness * roughness, 0.002);
+    out.metallic = material.metallic * metallic_roughness.b;
+    out.occlusion = 1.0 + material.occlusion_strength * (occlusion - 1.0);
+    out.emissive = material.emissive * textureSample(emissive_texture, emissive_sampler, in.tex_coords).rgb;
+    return out;
+}
+
+[[stage(fragment)]]
+fn fs_coverage(
+    in: VertexOutput,
+    [[builtin(front_facing)]] front_facing: bool,
+    ) -> [[location(0)]] vec4<f32> {
+    let surface = surface(in, front_facing);
+    return vec4<f32>(surface.emissive, surface.opacity);
+}
+
+// no screen space occlusion here, there's no depth behind a transparent surface to trace against
+[[stage(fragment)]]
+fn fs_ambient(
+    in: VertexOutput,
+    [[builtin(front_facing)]] front_facing: bool,
+    ) -> [[location(0)]] vec4<f32> {
+    let surface = surface(in, front_facing);
+    let radiance = ambient_light.radiance * surface.base_color * (1.0 - surface.metallic) * surface.occlusion;
+    return vec4<f32>(radiance, surface.opacity);
+}
+
+[[stage(fragment)]]
+fn fs_main(
+    in: VertexOutput,
+    [[builtin(front_facing)]] front_facing: bool,
+    ) -> [[location(0)]] vec4<f32> {
+    let surface = surface(in, front_facing);
+    let position = in.world_position;
+    let normal = surface.normal;
+    let shadow = not_occluded(position);
+    var result: vec4<f32> = vec4<f32>(0.0, 0.0, 0.0, surface.opacity);
+    if (shadow == 1.0) {
+      var w_i: vec3<f32> = -light.direction;
+      var attenuation: f32 = 1.0;
+      if (light.position.w != 0.0) {
+        w_i = light.position.xyz - position;
+        let r2 = dot(w_i, w_i);
+        w_i = normalize(w_i);
+        attenuation = 1.0 / (4.0 * PI * r2);
+
+        // spot cone falloff and range window, as KHR_lights_punctual suggests
+        let t = clamp((dot(-w_i, light.direction) - light.cone.x) / max(light.cone.y - light.cone.x, 0.0001), 0.0, 1.0);
+        attenuation = attenuation * t * t;
+        if (light.cone.z > 0.0) {
+          let d = sqrt(r2) / light.cone.z;
+          let window = clamp(1.0 - d * d * d * d, 0.0, 1.0);
+          attenuation = attenuation * window * window;
+        }
+      }
+      let w_o = to_eye(position);
+
+      // dielectrics are diffuse plus untinted specular, metals are only specular tinted by the base color
+      let spec = material.k_s * isotropic_microfacet(w_i, w_o, normal, material.eta, surface.alpha);
+      let dielectric = surface.base_color * (1.0 / PI) + vec3<f32>(spec, spec, spec);
+      let metal = conductor_microfacet(w_i, w_o, normal, surface.base_color, surface.alpha);
+
+      let brdf = mix(dielectric, metal, vec3<f32>(surface.metallic, surface.metallic, surface.metallic));
+
+      let k_light = light.power * max(dot(normal, w_i), 0.0) * attenuation;
+
+      result = vec4<f32>(brdf * k_light, surface.opacity);
+    }
+    return result;
+}
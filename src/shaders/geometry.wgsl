@@ -0,0 +1,279 @@
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] world_normal: vec3<f32>;
+    [[location(1)]] world_tangent: vec4<f32>;
+    [[location(2)]] tex_coords: vec2<f32>;
+};
+
+[[block]]
+struct Camera {
+    proj: mat4x4<f32>;
+    view: mat4x4<f32>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> cam_mats: Camera;
+
+struct Model {
+    model: mat4x4<f32>;
+    normal: mat4x4<f32>;
+};
+
+// one per instance
+[[block]]
+struct Instances {
+    models: array<Model>;
+};
+
+[[group(1), binding(0)]]
+var<storage, read> instances: Instances;
+
+// which instances this draw covers, after culling
+[[block]]
+struct VisibleInstances {
+    indices: array<u32>;
+};
+
+[[group(1), binding(15)]]
+var<storage, read> visible: VisibleInstances;
+
+[[block]]
+struct MatArray {
+    mats: array<mat4x4<f32>>;
+};
+
+[[group(1), binding(2)]]
+var<storage, read> joint_mats: MatArray;
+
+struct MorphDelta {
+    position: vec4<f32>;
+    normal: vec4<f32>;
+};
+
+[[block]]
+struct MorphDeltas {
+    deltas: array<MorphDelta>;
+};
+
+[[block]]
+struct MorphWeights {
+    target_count: u32;
+    vertex_count: u32;
+    // where the mesh's vertices and deltas start in the scene's shared buffers
+    first_vertex: u32;
+    first_delta: u32;
+    weights: array<f32>;
+};
+
+[[group(1), binding(13)]]
+var<storage, read> morph_deltas: MorphDeltas;
+
+[[group(1), binding(14)]]
+var<storage, read> morph_weights: MorphWeights;
+
+// morph targets move vertices in the mesh's own space, before skinning
+fn morph_position(position: vec3<f32>, vertex: u32) -> vec3<f32> {
+    var morphed = position;
+    for (var i: u32 = 0u; i < morph_weights.target_count; i = i + 1u) {
+        morphed = morphed + morph_weights.weights[i] * morph_deltas.deltas[morph_weights.first_delta + i * morph_weights.vertex_count + vertex - morph_weights.first_vertex].position.xyz;
+    }
+    return morphed;
+}
+
+fn morph_normal(normal: vec3<f32>, vertex: u32) -> vec3<f32> {
+    var morphed = normal;
+    for (var i: u32 = 0u; i < morph_weights.target_count; i = i + 1u) {
+        morphed = morphed + morph_weights.weights[i] * morph_deltas.deltas[morph_weights.first_delta + i * morph_weights.vertex_count + vertex - morph_weights.first_vertex].normal.xyz;
+    }
+    return morphed;
+}
+
+fn add_mats(m0: mat4x4<f32>, m1: mat4x4<f32>) -> mat4x4<f32> {
+    return mat4x4<f32>(m0.x + m1.x, m0.y + m1.y, m0.z + m1.z, m0.w + m1.w);
+}
+
+fn mul_scalar_mat(scalar: f32, mat: mat4x4<f32>) -> mat4x4<f32> {
+    return mat4x4<f32>(mat.x * scalar, mat.y * scalar, mat.z * scalar, mat.w * scalar);
+}
+
+fn mat4tomat3(m: mat4x4<f32>) -> mat3x3<f32> {
+    return mat3x3<f32>(m.x.xyz, m.y.xyz, m.z.xyz);
+}
+
+// position, normal and tangent are in the mesh's space, after morphing and skinning
+fn vertex_output(model_mats: Model, position: vec3<f32>, normal: vec3<f32>, tangent: vec4<f32>, tex_coords: vec2<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.world_normal = normalize((model_mats.normal * vec4<f32>(normal, 0.0)).xyz);
+    // tangents follow the surface, so they use the model matrix rather than the normal matrix
+    out.world_tangent = vec4<f32>(normalize((model_mats.model * vec4<f32>(tangent.xyz, 0.0)).xyz), tangent.w);
+    out.tex_coords = tex_coords;
+    out.position = cam_mats.proj * cam_mats.view * model_mats.model * vec4<f32>(position, 1.0);
+    return out;
+}
+
+// the blend of the vertex's joints, its weights sum to 1
+fn skin_mat(joints: vec4<u32>, weights: vec4<f32>) -> mat4x4<f32> {
+    return add_mats(
+      add_mats(
+          mul_scalar_mat(weights.x, joint_mats.mats[joints.x]),
+          mul_scalar_mat(weights.y, joint_mats.mats[joints.y]),
+      ),
+      add_mats(
+          mul_scalar_mat(weights.z, joint_mats.mats[joints.z]),
+          mul_scalar_mat(weights.w, joint_mats.mats[joints.w])
+      )
+    );
+}
+
+[[stage(vertex)]]
+fn vs_static(
+    [[builtin(vertex_index)]] vertex_index: u32,
+    [[builtin(instance_index)]] instance_index: u32,
+    [[location(0)]] position: vec3<f32>,
+    [[location(1)]] normal: vec4<f32>,
+    [[location(2)]] tex_coords: vec2<f32>,
+    [[location(3)]] tangent: vec4<f32>,
+) -> VertexOutput {
+    let instance = visible.indices[instance_index];
+    return vertex_output(instances.models[instance], morph_position(position, vertex_index), morph_normal(normal.xyz, vertex_index), tangent, tex_coords);
+}
+
+[[stage(vertex)]]
+fn vs_skinned(
+    [[builtin(vertex_index)]] vertex_index: u32,
+    [[builtin(instance_index)]] instance_index: u32,
+    [[location(0)]] position: vec3<f32>,
+    [[location(1)]] normal: vec4<f32>,
+    [[location(2)]] tex_coords: vec2<f32>,
+    [[location(3)]] tangent: vec4<f32>,
+    [[location(4)]] joints: vec4<u32>,
+    [[location(5)]] weights: vec4<f32>,
+) -> VertexOutput {
+    let instance = visible.indices[instance_index];
+    let bones_mat = skin_mat(joints, weights);
+    let skinned_position = (bones_mat * vec4<f32>(morph_position(position, vertex_index), 1.0)).xyz;
+    let skinned_normal = mat4tomat3(bones_mat) * morph_normal(normal.xyz, vertex_index);
+    let skinned_tangent = vec4<f32>(mat4tomat3(bones_mat) * tangent.xyz, tangent.w);
+    return vertex_output(instances.models[instance], skinned_position, skinned_normal, skinned_tangent, tex_coords);
+}
+
+// eight influences, the second four from JOINTS_1 and WEIGHTS_1
+[[stage(vertex)]]
+fn vs_skinned_x8(
+    [[builtin(vertex_index)]] vertex_index: u32,
+    [[builtin(instance_index)]] instance_index: u32,
+    [[location(0)]] position: vec3<f32>,
+    [[location(1)]] normal: vec4<f32>,
+    [[location(2)]] tex_coords: vec2<f32>,
+    [[location(3)]] tangent: vec4<f32>,
+    [[location(4)]] joints: vec4<u32>,
+    [[location(5)]] weights: vec4<f32>,
+    [[location(6)]] joints2: vec4<u32>,
+    [[location(7)]] weights2: vec4<f32>,
+) -> VertexOutput {
+    let instance = visible.indices[instance_index];
+    let bones_mat = add_mats(skin_mat(joints, weights), skin_mat(joints2, weights2));
+    let skinned_position = (bones_mat * vec4<f32>(morph_position(position, vertex_index), 1.0)).xyz;
+    let skinned_normal = mat4tomat3(bones_mat) * morph_normal(normal.xyz, vertex_index);
+    let skinned_tangent = vec4<f32>(mat4tomat3(bones_mat) * tangent.xyz, tangent.w);
+    return vertex_output(instances.models[instance], skinned_position, skinned_normal, skinned_tangent, tex_coords);
+}
+
+struct FragmentOutput {
+    [[location(0)]] diffuse: vec4<f32>;
+    [[location(1)]] material: vec4<f32>;
+    [[location(2)]] normal: vec4<f32>;
+    [[location(3)]] emissive: vec4<f32>;
+};
+
+[[block]]
+struct Material {
+    roughness: f32;
+    k_s: f32;
+    eta: f32;
+    diffuse: vec3<f32>;
+    normal_scale: f32;
+    occlusion_strength: f32;
+    metallic: f32;
+    opacity: f32;
+    alpha_cutoff: f32;
+    emissive: vec3<f32>;
+};
+
+[[group(1), binding(1)]]
+var<uniform> material: Material;
+
+[[group(1), binding(3)]]
+var base_color_texture: texture_2d<f32>;
+[[group(1), binding(4)]]
+var metallic_roughness_texture: texture_2d<f32>;
+[[group(1), binding(5)]]
+var normal_texture: texture_2d<f32>;
+[[group(1), binding(6)]]
+var occlusion_texture: texture_2d<f32>;
+[[group(1), binding(7)]]
+var emissive_texture: texture_2d<f32>;
+
+[[group(1), binding(8)]]
+var base_color_sampler: sampler;
+[[group(1), binding(9)]]
+var metallic_roughness_sampler: sampler;
+[[group(1), binding(10)]]
+var normal_sampler: sampler;
+[[group(1), binding(11)]]
+var occlusion_sampler: sampler;
+[[group(1), binding(12)]]
+var emissive_sampler: sampler;
+
+fn g_buffer(in: VertexOutput, front_facing: bool) -> FragmentOutput {
+    var out: FragmentOutput;
+    let base_color = textureSample(base_color_texture, base_color_sampler, in.tex_coords);
+    let metallic_roughness = textureSample(metallic_roughness_texture, metallic_roughness_sampler, in.tex_coords);
+    let occlusion = textureSample(occlusion_texture, occlusion_sampler, in.tex_coords).r;
+
+    // normal map is in tangent space, scaled in x and y like the gltf spec says
+    let n = normalize(in.world_normal);
+    let t = normalize(in.world_tangent.xyz - n * dot(n, in.world_tangent.xyz));
+    let b = cross(n, t) * in.world_tangent.w;
+    let tangent_normal = (textureSample(normal_texture, normal_sampler, in.tex_coords).xyz * 2.0 - 1.0) * vec3<f32>(material.normal_scale, material.normal_scale, 1.0);
+    var normal: vec3<f32> = normalize(mat3x3<f32>(t, b, n) * tangent_normal);
+    if (!front_facing) {
+      normal = -normal;
+    }
+
+    // ambient occlusion goes in the otherwise unused w of the normal
+    out.normal = vec4<f32>(normal, 1.0 + material.occlusion_strength * (occlusion - 1.0));
+    // roughness is green and metalness blue. alpha is kept off 0, where the GGX distribution degenerates
+    let roughness = material.roughness * metallic_roughness.g;
+    let metallic = material.metallic * metallic_roughness.b;
+    out.material = vec4<f32>(max(roughness * roughness, 0.002), material.k_s, material.eta, metallic);
+    out.diffuse = vec4<f32>(material.diffuse * base_color.rgb, 1.0);
+    out.emissive = vec4<f32>(material.emissive * textureSample(emissive_texture, emissive_sampler, in.tex_coords).rgb, 1.0);
+    return out;
+}
+
+
+fn opacity(tex_coords: vec2<f32>) -> f32 {
+    return material.opacity * textureSample(base_color_texture, base_color_sampler, tex_coords).a;
+}
+
+[[stage(fragment)]]
+fn fs_main(
+    in: VertexOutput,
+    [[builtin(front_facing)]] front_facing: bool,
+    ) -> FragmentOutput {
+    return g_buffer(in, front_facing);
+}
+
+// alpha masked materials, kept apart so opaque ones don't lose early depth testing to the discard
+[[stage(fragment)]]
+fn fs_masked(
+    in: VertexOutput,
+    [[builtin(front_facing)]] front_facing: bool,
+    ) -> FragmentOutput {
+    let out = g_buffer(in, front_facing);
+    if (opacity(in.tex_coords) < material.alpha_cutoff) {
+        discard;
+    }
+    return out;
+}
@@ -0,0 +1,45 @@
+// builds the hierarchical depth pyramid the culling pass tests against. every texel holds the furthest depth under it
+
+[[group(0), binding(0)]]
+var depth: texture_depth_2d;
+
+[[group(0), binding(1)]]
+var level_out: texture_storage_2d<r32float, write>;
+
+// level 0 is a straight copy of the depth buffer
+[[stage(compute), workgroup_size(8, 8)]]
+fn copy_depth([[builtin(global_invocation_id)]] id: vec3<u32>) {
+    let size = textureDimensions(depth);
+    let coords = vec2<i32>(id.xy);
+    if (coords.x >= size.x || coords.y >= size.y) {
+        return;
+    }
+    textureStore(level_out, coords, vec4<f32>(textureLoad(depth, coords, 0), 0.0, 0.0, 0.0));
+}
+
+[[group(0), binding(0)]]
+var level_in: texture_2d<f32>;
+
+// each texel is the max of the 2x2 texels above it, plus the next row and column when the level above has an odd size
+// so nothing at the edges gets dropped
+[[stage(compute), workgroup_size(8, 8)]]
+fn downsample([[builtin(global_invocation_id)]] id: vec3<u32>) {
+    let size_in = textureDimensions(level_in, 0);
+    let size_out = max(size_in / 2, vec2<i32>(1, 1));
+    let coords = vec2<i32>(id.xy);
+    if (coords.x >= size_out.x || coords.y >= size_out.y) {
+        return;
+    }
+    let extra_x = select(0, 1, coords.x == size_out.x - 1 && size_in.x % 2 == 1);
+    let extra_y = select(0, 1, coords.y == size_out.y - 1 && size_in.y % 2 == 1);
+    var furthest = 0.0;
+    for (var y: i32 = 0; y < 2 + extra_y; y = y + 1) {
+        for (var x: i32 = 0; x < 2 + extra_x; x = x + 1) {
+            let texel = coords * 2 + vec2<i32>(x, y);
+            if (texel.x < size_in.x && texel.y < size_in.y) {
+                furthest = max(furthest, textureLoad(level_in, texel, 0).x);
+            }
+        }
+    }
+    textureStore(level_out, coords, vec4<f32>(furthest, 0.0, 0.0, 0.0));
+}
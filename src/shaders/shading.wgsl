@@ -28,7 +28,11 @@ struct Light {
     proj: mat4x4<f32>;
     view: mat4x4<f32>;
     power: vec3<f32>;
-    position: vec3<f32>;
+    // w is 0 for directional lights, which have irradiance in power instead
+    position: vec4<f32>;
+    direction: vec3<f32>;
+    // cos outer angle, cos inner angle, range (0 for none)
+    cone: vec3<f32>;
 };
 
 [[group(0), binding(0)]]
@@ -39,6 +43,8 @@ struct Camera_Pos {
     inv_proj: mat4x4<f32>;
     inv_view: mat4x4<f32>;
     position: vec3<f32>;
+    // the way the camera looks, w is 1 when it's orthographic and every view ray points this way
+    forward: vec4<f32>;
 };
 
 [[group(1), binding(1)]]
@@ -145,6 +151,25 @@ fn isotropic_microfacet(i: vec3<f32>, o: vec3<f32>, n: vec3<f32>, eta: f32, alph
     return F * G * D(m, n, alpha) / (4.0 * idotn * odotn);
 }
 
+// The same microfacet BRDF for a conductor, with Schlick's Fresnel approximation
+// tinted by the base color instead of the dielectric Fresnel term
+//   f0 -- reflectance at normal incidence (the base color)
+// return: RGB BRDF value
+fn conductor_microfacet(i: vec3<f32>, o: vec3<f32>, n: vec3<f32>, f0: vec3<f32>, alpha: f32) -> vec3<f32> {
+    let odotn = dot(o, n);
+    let m = normalize(i + o);
+
+    let idotn = dot(i,n);
+    if (idotn <= 0.0 || odotn <= 0.0) {
+        return vec3<f32>(0.0, 0.0, 0.0);
+    }
+
+    let idotm = max(dot(i, m), 0.0);
+    let F = f0 + (vec3<f32>(1.0, 1.0, 1.0) - f0) * pow(1.0 - idotm, 5.0);
+    let G = G1(i, m, n, alpha) * G1(o, m, n, alpha);
+    return F * G * D(m, n, alpha) / (4.0 * idotn * odotn);
+}
+
 fn get_world_position(tex_coords: vec2<f32>) -> vec3<f32> {
     let depth = textureSample(depth_texture, depth_sampler, tex_coords);
     let coords_ndc = vec2<f32>(tex_coords.x * 2.0 - 1.0, (1.0 - tex_coords.y) * 2.0 - 1.0);
@@ -165,25 +190,51 @@ fn not_occluded(position: vec3<f32>) -> f32 {
     return textureSampleCompare(light_depth_texture, light_depth_sampler, shadow_coords.xy, shadow_coords.z);
 }
 
+// from a point towards the eye, which under an orthographic projection is back along the camera's forward axis
+// wherever the point is
+fn to_eye(position: vec3<f32>) -> vec3<f32> {
+    if (camera.forward.w > 0.0) {
+        return -camera.forward.xyz;
+    }
+    return normalize(camera.position - position);
+}
+
 [[stage(fragment)]]
 fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
     let diffuse = textureSample(diffuse_texture, diffuse_sampler, in.tex_coords).xyz;
     let normal = textureSample(normal_texture, normal_sampler, in.tex_coords).xyz;
-    let material = textureSample(material_texture, material_sampler, in.tex_coords).xyz;
+    let material = textureSample(material_texture, material_sampler, in.tex_coords);
     let position = get_world_position(in.tex_coords);
     let shadow = not_occluded(position);
     var result: vec4<f32> = vec4<f32>(0.0, 0.0, 0.0, 1.0);
     if (shadow == 1.0) {
-      var w_i: vec3<f32> = light.position - position;
-      let w_o = normalize(camera.position - position);
-      let r2 = dot(w_i, w_i);
-      w_i = normalize(w_i);
-
+      var w_i: vec3<f32> = -light.direction;
+      var attenuation: f32 = 1.0;
+      if (light.position.w != 0.0) {
+        w_i = light.position.xyz - position;
+        let r2 = dot(w_i, w_i);
+        w_i = normalize(w_i);
+        attenuation = 1.0 / (4.0 * PI * r2);
+
+        // spot cone falloff and range window, as KHR_lights_punctual suggests
+        let t = clamp((dot(-w_i, light.direction) - light.cone.x) / max(light.cone.y - light.cone.x, 0.0001), 0.0, 1.0);
+        attenuation = attenuation * t * t;
+        if (light.cone.z > 0.0) {
+          let d = sqrt(r2) / light.cone.z;
+          let window = clamp(1.0 - d * d * d * d, 0.0, 1.0);
+          attenuation = attenuation * window * window;
+        }
+      }
+      let w_o = to_eye(position);
+
+      // dielectrics are diffuse plus untinted specular, metals are only specular tinted by the base color
       let spec = material.y * isotropic_microfacet(w_i, w_o, normal, material.z, material.x);
+      let dielectric = diffuse * (1.0 / PI) + vec3<f32>(spec, spec, spec);
+      let metal = conductor_microfacet(w_i, w_o, normal, diffuse, material.x);
 
-      let brdf = diffuse * (1.0 / PI) + vec3<f32>(spec, spec, spec);
+      let brdf = mix(dielectric, metal, vec3<f32>(material.w, material.w, material.w));
 
-      let k_light = light.power * max(dot(normal, w_i), 0.0) * (1.0 / (4.0 * PI * r2));
+      let k_light = light.power * max(dot(normal, w_i), 0.0) * attenuation;
 
       result = vec4<f32>(brdf * k_light, 1.0);
     }
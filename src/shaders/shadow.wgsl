@@ -0,0 +1,184 @@
+[[block]]
+struct Light {
+    proj: mat4x4<f32>;
+    view: mat4x4<f32>;
+};
+
+struct Model {
+    model: mat4x4<f32>;
+    normal: mat4x4<f32>;
+};
+
+// one per instance
+[[block]]
+struct Instances {
+    models: array<Model>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> light: Light;
+
+[[group(1), binding(0)]]
+var<storage, read> instances: Instances;
+
+// which instances this draw covers, after culling
+[[block]]
+struct VisibleInstances {
+    indices: array<u32>;
+};
+
+[[group(1), binding(15)]]
+var<storage, read> visible: VisibleInstances;
+
+[[block]]
+struct MatArray {
+    mats: array<mat4x4<f32>>;
+};
+
+[[group(1), binding(2)]]
+var<storage, read> joint_mats: MatArray;
+
+struct MorphDelta {
+    position: vec4<f32>;
+    normal: vec4<f32>;
+};
+
+[[block]]
+struct MorphDeltas {
+    deltas: array<MorphDelta>;
+};
+
+[[block]]
+struct MorphWeights {
+    target_count: u32;
+    vertex_count: u32;
+    // where the mesh's vertices and deltas start in the scene's shared buffers
+    first_vertex: u32;
+    first_delta: u32;
+    weights: array<f32>;
+};
+
+[[group(1), binding(13)]]
+var<storage, read> morph_deltas: MorphDeltas;
+
+[[group(1), binding(14)]]
+var<storage, read> morph_weights: MorphWeights;
+
+// morph targets move vertices in the mesh's own space, before skinning
+fn morph_position(position: vec3<f32>, vertex: u32) -> vec3<f32> {
+    var morphed = position;
+    for (var i: u32 = 0u; i < morph_weights.target_count; i = i + 1u) {
+        morphed = morphed + morph_weights.weights[i] * morph_deltas.deltas[morph_weights.first_delta + i * morph_weights.vertex_count + vertex - morph_weights.first_vertex].position.xyz;
+    }
+    return morphed;
+}
+
+fn add_mats(m0: mat4x4<f32>, m1: mat4x4<f32>) -> mat4x4<f32> {
+    return mat4x4<f32>(m0.x + m1.x, m0.y + m1.y, m0.z + m1.z, m0.w + m1.w);
+}
+
+fn mul_scalar_mat(scalar: f32, mat: mat4x4<f32>) -> mat4x4<f32> {
+    return mat4x4<f32>(mat.x * scalar, mat.y * scalar, mat.z * scalar, mat.w * scalar);
+}
+
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] tex_coords: vec2<f32>;
+};
+
+// the blend of the vertex's joints, its weights sum to 1
+fn skin_mat(joints: vec4<u32>, weights: vec4<f32>) -> mat4x4<f32> {
+    return add_mats(
+      add_mats(
+          mul_scalar_mat(weights.x, joint_mats.mats[joints.x]),
+          mul_scalar_mat(weights.y, joint_mats.mats[joints.y]),
+      ),
+      add_mats(
+          mul_scalar_mat(weights.z, joint_mats.mats[joints.z]),
+          mul_scalar_mat(weights.w, joint_mats.mats[joints.w])
+      )
+    );
+}
+
+[[stage(vertex)]]
+fn vs_static(
+    [[builtin(vertex_index)]] vertex_index: u32,
+    [[builtin(instance_index)]] instance_index: u32,
+    [[location(0)]] position: vec3<f32>,
+    [[location(2)]] tex_coords: vec2<f32>,
+) -> VertexOutput {
+    let instance = visible.indices[instance_index];
+    var out: VertexOutput;
+    out.position = light.proj * light.view * instances.models[instance].model * vec4<f32>(morph_position(position, vertex_index), 1.0);
+    out.tex_coords = tex_coords;
+    return out;
+}
+
+[[stage(vertex)]]
+fn vs_skinned(
+    [[builtin(vertex_index)]] vertex_index: u32,
+    [[builtin(instance_index)]] instance_index: u32,
+    [[location(0)]] position: vec3<f32>,
+    [[location(2)]] tex_coords: vec2<f32>,
+    [[location(4)]] joints: vec4<u32>,
+    [[location(5)]] weights: vec4<f32>,
+) -> VertexOutput {
+    let instance = visible.indices[instance_index];
+    let model_mats = instances.models[instance];
+    var out: VertexOutput;
+    out.position = light.proj * light.view * model_mats.model * skin_mat(joints, weights) * vec4<f32>(morph_position(position, vertex_index), 1.0);
+    out.tex_coords = tex_coords;
+    return out;
+}
+
+// eight influences, the second four from JOINTS_1 and WEIGHTS_1
+[[stage(vertex)]]
+fn vs_skinned_x8(
+    [[builtin(vertex_index)]] vertex_index: u32,
+    [[builtin(instance_index)]] instance_index: u32,
+    [[location(0)]] position: vec3<f32>,
+    [[location(2)]] tex_coords: vec2<f32>,
+    [[location(4)]] joints: vec4<u32>,
+    [[location(5)]] weights: vec4<f32>,
+    [[location(6)]] joints2: vec4<u32>,
+    [[location(7)]] weights2: vec4<f32>,
+) -> VertexOutput {
+    let instance = visible.indices[instance_index];
+    let model_mats = instances.models[instance];
+    let bones_mat = add_mats(skin_mat(joints, weights), skin_mat(joints2, weights2));
+    var out: VertexOutput;
+    out.position = light.proj * light.view * model_mats.model * bones_mat * vec4<f32>(morph_position(position, vertex_index), 1.0);
+    out.tex_coords = tex_coords;
+    return out;
+}
+
+[[block]]
+struct Material {
+    roughness: f32;
+    k_s: f32;
+    eta: f32;
+    diffuse: vec3<f32>;
+    normal_scale: f32;
+    occlusion_strength: f32;
+    metallic: f32;
+    opacity: f32;
+    alpha_cutoff: f32;
+    emissive: vec3<f32>;
+};
+
+[[group(1), binding(1)]]
+var<uniform> material: Material;
+
+[[group(1), binding(3)]]
+var base_color_texture: texture_2d<f32>;
+[[group(1), binding(8)]]
+var base_color_sampler: sampler;
+
+// only alpha masked materials need a fragment stage, to cut their holes out of the shadow map
+[[stage(fragment)]]
+fn fs_masked(in: VertexOutput) {
+    if (material.opacity * textureSample(base_color_texture, base_color_sampler, in.tex_coords).a < material.alpha_cutoff) {
+        discard;
+    }
+}
+
@@ -0,0 +1,165 @@
+// compute skinning: one invocation per vertex of every skinned mesh, morphing and skinning it once a frame into a static
+// vertex that every pass then draws. vertices are read and written as raw words in the layouts of mesh.rs
+
+// a skinned mesh's vertices, and where its joints, morphs and output are
+struct SkinJob {
+    // in the arena's skinned vertex buffer for its format
+    first_vertex: u32;
+    vertex_count: u32;
+    // in the output buffer
+    first_output: u32;
+    // 0 for four u8 joints, 1 for four u16 joints, 2 for eight u16 joints
+    format: u32;
+    // in the joint matrices buffer
+    first_joint: u32;
+    // index of the mesh's morph weights header in the morph weights buffer, in words
+    morph_weights: u32;
+    target_count: u32;
+    first_delta: u32;
+};
+
+[[block]]
+struct SkinJobs {
+    jobs: array<SkinJob>;
+};
+
+[[block]]
+struct Words {
+    words: array<u32>;
+};
+
+[[block]]
+struct MatArray {
+    mats: array<mat4x4<f32>>;
+};
+
+struct MorphDelta {
+    position: vec4<f32>;
+    normal: vec4<f32>;
+};
+
+[[block]]
+struct MorphDeltas {
+    deltas: array<MorphDelta>;
+};
+
+[[group(0), binding(0)]]
+var<storage, read> jobs: SkinJobs;
+
+[[group(0), binding(1)]]
+var<storage, read> vertices8: Words;
+
+[[group(0), binding(2)]]
+var<storage, read> vertices16: Words;
+
+[[group(0), binding(3)]]
+var<storage, read> joint_mats: MatArray;
+
+[[group(0), binding(4)]]
+var<storage, read> morph_deltas: MorphDeltas;
+
+[[group(0), binding(5)]]
+var<storage, read> morph_weights: Words;
+
+[[group(0), binding(6)]]
+var<storage, read_write> output: Words;
+
+[[group(0), binding(7)]]
+var<storage, read> vertices16x8: Words;
+
+// words per SkinnedVertex8, SkinnedVertex16, SkinnedVertex16x8 and StaticVertex
+let SKINNED_8_WORDS: u32 = 12u;
+let SKINNED_16_WORDS: u32 = 13u;
+let SKINNED_16X8_WORDS: u32 = 17u;
+let STATIC_WORDS: u32 = 9u;
+
+fn add_mats(m0: mat4x4<f32>, m1: mat4x4<f32>) -> mat4x4<f32> {
+    return mat4x4<f32>(m0.x + m1.x, m0.y + m1.y, m0.z + m1.z, m0.w + m1.w);
+}
+
+fn mul_scalar_mat(scalar: f32, mat: mat4x4<f32>) -> mat4x4<f32> {
+    return mat4x4<f32>(mat.x * scalar, mat.y * scalar, mat.z * scalar, mat.w * scalar);
+}
+
+fn mat4tomat3(m: mat4x4<f32>) -> mat3x3<f32> {
+    return mat3x3<f32>(m.x.xyz, m.y.xyz, m.z.xyz);
+}
+
+// x is the vertex, y the job
+[[stage(compute), workgroup_size(64)]]
+fn main([[builtin(global_invocation_id)]] id: vec3<u32>) {
+    let job = jobs.jobs[id.y];
+    if (id.x >= job.vertex_count) {
+        return;
+    }
+
+    let vertex = job.first_vertex + id.x;
+    var words: array<u32, 17>;
+    if (job.format == 2u) {
+        for (var i: u32 = 0u; i < SKINNED_16X8_WORDS; i = i + 1u) {
+            words[i] = vertices16x8.words[vertex * SKINNED_16X8_WORDS + i];
+        }
+    } elseif (job.format == 1u) {
+        for (var i: u32 = 0u; i < SKINNED_16_WORDS; i = i + 1u) {
+            words[i] = vertices16.words[vertex * SKINNED_16_WORDS + i];
+        }
+    } else {
+        for (var i: u32 = 0u; i < SKINNED_8_WORDS; i = i + 1u) {
+            words[i] = vertices8.words[vertex * SKINNED_8_WORDS + i];
+        }
+    }
+
+    var position = vec3<f32>(bitcast<f32>(words[0]), bitcast<f32>(words[1]), bitcast<f32>(words[2]));
+    var normal = vec3<f32>(unpack2x16snorm(words[3]), unpack2x16snorm(words[4]).x);
+    let tangent = vec4<f32>(unpack2x16snorm(words[7]), unpack2x16snorm(words[8]));
+    // u8 joints are four to a word, u16 joints and unorm16 weights two, and the weights follow the joints
+    var joints: array<u32, 8>;
+    var weights: array<f32, 8>;
+    var influences: u32 = 4u;
+    var first_weight: u32 = 11u;
+    if (job.format == 0u) {
+        first_weight = 10u;
+    } elseif (job.format == 2u) {
+        influences = 8u;
+        first_weight = 13u;
+    }
+    for (var i: u32 = 0u; i < influences; i = i + 1u) {
+        if (job.format == 0u) {
+            joints[i] = (words[9] >> (i * 8u)) & 0xffu;
+        } else {
+            joints[i] = (words[9u + i / 2u] >> (i % 2u * 16u)) & 0xffffu;
+        }
+        let pair = unpack2x16unorm(words[first_weight + i / 2u]);
+        weights[i] = select(pair.x, pair.y, i % 2u == 1u);
+    }
+
+    // morph targets move vertices in the mesh's own space, before skinning
+    for (var i: u32 = 0u; i < job.target_count; i = i + 1u) {
+        let weight = bitcast<f32>(morph_weights.words[job.morph_weights + 4u + i]);
+        let delta = morph_deltas.deltas[job.first_delta + i * job.vertex_count + id.x];
+        position = position + weight * delta.position.xyz;
+        normal = normal + weight * delta.normal.xyz;
+    }
+
+    var bones_mat = mat4x4<f32>(vec4<f32>(0.0), vec4<f32>(0.0), vec4<f32>(0.0), vec4<f32>(0.0));
+    for (var i: u32 = 0u; i < influences; i = i + 1u) {
+        let joint = job.first_joint + joints[i];
+        bones_mat = add_mats(bones_mat, mul_scalar_mat(weights[i], joint_mats.mats[joint]));
+    }
+    let skinned_position = (bones_mat * vec4<f32>(position, 1.0)).xyz;
+    // stored as snorm16, so they have to be unit length
+    let skinned_normal = normalize(mat4tomat3(bones_mat) * normal);
+    let skinned_tangent = normalize(mat4tomat3(bones_mat) * tangent.xyz);
+
+    let out = (job.first_output + id.x) * STATIC_WORDS;
+    output.words[out] = bitcast<u32>(skinned_position.x);
+    output.words[out + 1u] = bitcast<u32>(skinned_position.y);
+    output.words[out + 2u] = bitcast<u32>(skinned_position.z);
+    output.words[out + 3u] = pack2x16snorm(skinned_normal.xy);
+    output.words[out + 4u] = pack2x16snorm(vec2<f32>(skinned_normal.z, 0.0));
+    // tex coords pass straight through
+    output.words[out + 5u] = words[5];
+    output.words[out + 6u] = words[6];
+    output.words[out + 7u] = pack2x16snorm(skinned_tangent.xy);
+    output.words[out + 8u] = pack2x16snorm(vec2<f32>(skinned_tangent.z, tangent.w));
+}
@@ -0,0 +1,174 @@
+use wgpu::*;
+use wgpu::util::DeviceExt;
+use bytemuck::{Pod, Zeroable};
+use glam::Mat4;
+use std::borrow::Cow;
+use crate::mesh::VertexKind;
+use crate::scene::Scene;
+
+const WORKGROUP_SIZE: u32 = 64;
+
+// the layout of SkinJob in skin.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct SkinJob {
+    first_vertex: u32,
+    vertex_count: u32,
+    first_output: u32,
+    format: u32,
+    first_joint: u32,
+    morph_weights: u32,
+    target_count: u32,
+    first_delta: u32,
+}
+
+// skins and morphs every skinned mesh once a frame in a compute pass, into static vertices the geometry, shadow and
+// forward passes all draw with their static pipelines. otherwise a skinned mesh would be skinned again in every pass
+// that draws it, which with a few shadowing lights is most of the work
+pub struct GpuSkinning {
+    pipeline: ComputePipeline,
+    bind_group: BindGroup,
+    // every skinned mesh's vertices in StaticVertex's layout, rewritten every frame
+    pub vertices: Buffer,
+    // what to add to a mesh's indices to get to its vertices in the output, 0 for meshes that aren't skinned here
+    base_vertices: Vec<i32>,
+    job_count: u32,
+    // vertices in the biggest job, which sizes the dispatch
+    max_vertices: u32,
+}
+
+impl GpuSkinning {
+    pub fn supported(adapter: &Adapter) -> bool {
+        adapter.get_downlevel_properties().flags.contains(DownlevelFlags::COMPUTE_SHADERS)
+    }
+
+    // marks every skinned mesh as gpu_skinned, so its morph weights header leaves its already morphed vertices alone
+    pub fn new(device: &Device, scene: &mut Scene) -> Self {
+        let storage = |binding, read_only| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                storage(0, true),
+                storage(1, true),
+                storage(2, true),
+                storage(3, true),
+                storage(4, true),
+                storage(5, true),
+                storage(6, false),
+                storage(7, true),
+            ],
+            label: Some("skinning layout"),
+        });
+        let pipeline = {
+            let module = device.create_shader_module(&ShaderModuleDescriptor {
+                label: Some("skinning module"),
+                source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("shaders/skin.wgsl"))),
+            });
+            let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("skinning pipeline layout"),
+                bind_group_layouts: &[&layout],
+                push_constant_ranges: &[],
+            });
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some("skinning pipeline"),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point: "main",
+            })
+        };
+
+        let buffers = &scene.mesh_buffers;
+        let mut jobs = Vec::new();
+        let mut base_vertices = vec![0; scene.meshes.len()];
+        let mut output_vertices = 0;
+        for (i, mesh) in scene.meshes.iter_mut().enumerate() {
+            if mesh.vertex_kind() == VertexKind::Static {
+                continue;
+            }
+            let geometry = &mesh.geometry;
+            jobs.push(SkinJob {
+                first_vertex: geometry.first_vertex,
+                vertex_count: geometry.vertex_count,
+                first_output: output_vertices,
+                // Skinned8, Skinned16 and Skinned16x8 are 0, 1 and 2
+                format: mesh.vertex_kind().index() as u32 - 1,
+                first_joint: (buffers.joint_matrices.offset(i) / std::mem::size_of::<Mat4>() as BufferAddress) as u32,
+                morph_weights: (buffers.morph_weights.offset(i) / std::mem::size_of::<u32>() as BufferAddress) as u32,
+                target_count: geometry.morph_targets,
+                first_delta: geometry.first_delta,
+            });
+            base_vertices[i] = output_vertices as i32 - geometry.first_vertex as i32;
+            output_vertices += geometry.vertex_count;
+            mesh.gpu_skinned = true;
+        }
+
+        let job_count = jobs.len() as u32;
+        let max_vertices = jobs.iter().map(|j| j.vertex_count).max().unwrap_or(0);
+        // empty buffers can't be bound
+        if jobs.is_empty() {
+            jobs.push(SkinJob::zeroed());
+        }
+        let jobs = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("skinning jobs buffer"),
+            contents: bytemuck::cast_slice(&jobs),
+            usage: BufferUsages::STORAGE,
+        });
+        let vertices = device.create_buffer(&BufferDescriptor {
+            label: Some("skinned vertices buffer"),
+            size: (output_vertices.max(1) as usize * VertexKind::Static.size()) as BufferAddress,
+            usage: BufferUsages::STORAGE | BufferUsages::VERTEX,
+            mapped_at_creation: false,
+        });
+
+        let resources = [
+            jobs.as_entire_binding(),
+            scene.arena.vertices(VertexKind::Skinned8).as_entire_binding(),
+            scene.arena.vertices(VertexKind::Skinned16).as_entire_binding(),
+            buffers.joint_matrices.buffer.as_entire_binding(),
+            scene.arena.morph_deltas.as_entire_binding(),
+            buffers.morph_weights.buffer.as_entire_binding(),
+            vertices.as_entire_binding(),
+            scene.arena.vertices(VertexKind::Skinned16x8).as_entire_binding(),
+        ];
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout: &layout,
+            entries: &resources.into_iter().enumerate().map(|(binding, resource)| BindGroupEntry {
+                binding: binding as u32,
+                resource,
+            }).collect::<Vec<_>>(),
+            label: Some("skinning bind group"),
+        });
+
+        Self {
+            pipeline,
+            bind_group,
+            vertices,
+            base_vertices,
+            job_count,
+            max_vertices,
+        }
+    }
+
+    // call after the scene's pose is uploaded, and before anything draws the skinned meshes
+    pub fn record(&self, encoder: &mut CommandEncoder) {
+        if self.job_count == 0 {
+            return;
+        }
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor { label: Some("skinning pass") });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.dispatch(self.max_vertices.div_ceil(WORKGROUP_SIZE), self.job_count, 1);
+    }
+
+    pub fn base_vertex(&self, mesh: usize) -> i32 {
+        self.base_vertices[mesh]
+    }
+}
@@ -4,9 +4,11 @@ use glam::{Vec2, Vec3, Vec4};
 use core::ops::{Mul, Add};
 use std::f32::consts::PI;
 use crevice::std140::{AsStd140, Std140};
+use crate::strategy::BindGroupStrategy;
 
 pub struct Sky {
     pub bind_group: BindGroup,
+    pub buffer: Buffer,
     pub layout: BindGroupLayout,
 }
 
@@ -170,10 +172,28 @@ impl Sky {
 
 
         Self {
-            bind_group, 
+            bind_group,
+            buffer,
             layout,
         }
     }
+
+    // bind group for this frame under strategies that don't reuse the one made at load
+    pub fn frame_bind_group(&self, strategy: BindGroupStrategy, device: &Device) -> Option<BindGroup> {
+        if strategy != BindGroupStrategy::Sky {
+            return None;
+        }
+        Some(device.create_bind_group(&BindGroupDescriptor {
+            layout: &self.layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: self.buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("sky bind group"),
+        }))
+    }
 }
 
 #[derive(AsStd140)]
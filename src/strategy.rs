@@ -0,0 +1,65 @@
+use std::fmt;
+use std::str::FromStr;
+use anyhow::{Error, anyhow};
+use serde::{Serialize, Serializer};
+
+// which bind groups (and buffers) get rebuilt every frame rather than once at load.
+// each of these used to be its own copy of the renderer (src_clean, src_lights, ...),
+// so the names are kept the same to line up with older results
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum BindGroupStrategy {
+    // everything is created once when the scene is loaded
+    #[default]
+    Clean,
+    // light bind groups are created every frame from a persistent light buffer
+    Lights,
+    // light buffers and bind groups are both created every frame from the raw light data
+    Lights2,
+    // mesh bind groups are created every frame
+    Mesh,
+    // the sky bind group is created every frame
+    Sky,
+}
+
+impl BindGroupStrategy {
+    pub const ALL: [BindGroupStrategy; 5] = [
+        BindGroupStrategy::Clean,
+        BindGroupStrategy::Lights,
+        BindGroupStrategy::Lights2,
+        BindGroupStrategy::Mesh,
+        BindGroupStrategy::Sky,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BindGroupStrategy::Clean => "clean",
+            BindGroupStrategy::Lights => "lights",
+            BindGroupStrategy::Lights2 => "lights2",
+            BindGroupStrategy::Mesh => "mesh",
+            BindGroupStrategy::Sky => "sky",
+        }
+    }
+}
+
+impl fmt::Display for BindGroupStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for BindGroupStrategy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BindGroupStrategy::ALL.iter()
+            .find(|strategy| strategy.name() == s)
+            .copied()
+            .ok_or(anyhow!("Unknown strategy '{}', expected one of: clean, lights, lights2, mesh, sky", s))
+    }
+}
+
+impl Serialize for BindGroupStrategy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
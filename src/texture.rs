@@ -0,0 +1,306 @@
+use wgpu::*;
+use core::num::NonZeroU32;
+use gltf::image::Format;
+use gltf::texture::{MagFilter, MinFilter, WrappingMode};
+
+pub struct Texture {
+    pub texture: wgpu::Texture,
+    pub view: TextureView,
+    pub sampler: Sampler,
+    pub bind_group: BindGroup,
+    pub format: TextureFormat,
+}
+
+impl Texture {
+    pub fn create_window_texture(device: &Device, layout: &BindGroupLayout, format: TextureFormat, compare: Option<CompareFunction>, width: u32, height: u32) -> Self {
+        let texture = device.create_texture(&TextureDescriptor {
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            label: Some("texture"),
+        });
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Nearest,
+            lod_min_clamp: -100.0,
+            lod_max_clamp: 100.0,
+            compare,
+            ..Default::default()
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&sampler),
+                }
+            ],
+            label: Some(&format!("{:?}", format)),
+        });
+
+
+        Self { texture, view, sampler, bind_group, format }
+    }
+}
+
+pub struct MipTexture {
+    pub texture: wgpu::Texture,
+    pub sampler: Sampler,
+    pub bind_groups: Vec<BindGroup>,
+    pub views: Vec<TextureView>,
+    pub format: TextureFormat,
+    pub mip_level_count: u32,
+}
+
+impl MipTexture {
+    pub fn new(device: &Device, layout: &BindGroupLayout, width: u32, height: u32, mip_level_count: u32) -> Self {
+        let format = TextureFormat::Rgba16Float;
+
+        let texture = device.create_texture(&TextureDescriptor {
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count,
+            format,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            label: Some("mip texture"),
+        });
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Linear,
+            lod_min_clamp: -100.0,
+            lod_max_clamp: 100.0,
+            ..Default::default()
+        });
+
+        let views: Vec<TextureView> = (0..mip_level_count).map(|i| {
+            texture.create_view(&TextureViewDescriptor {
+                label: Some("mip"),
+                format: None,
+                dimension: None,
+                aspect: TextureAspect::All,
+                base_mip_level: i,
+                mip_level_count: NonZeroU32::new(1),
+                base_array_layer: 0,
+                array_layer_count: None,
+            })
+        }).collect();
+
+
+        let bind_groups = (0..mip_level_count).map(|i| {
+            device.create_bind_group(&BindGroupDescriptor {
+                layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(&views[i as usize]),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(&sampler),
+                    },
+                ],
+                label: Some(&format!("{:?} mip", format)),
+            })
+        }).collect();
+
+
+        Self { texture, sampler, bind_groups, format, mip_level_count, views }
+    }
+
+    pub fn generate_mipmaps(&self, pipeline: &RenderPipeline, encoder: &mut CommandEncoder) {
+        for i in 1..self.mip_level_count as usize {
+            let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("mip pass"),
+                color_attachments: &[RenderPassColorAttachment {
+                    view: &self.views[i],
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::WHITE),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+            rpass.set_pipeline(pipeline);
+            rpass.set_bind_group(0, &self.bind_groups[i - 1], &[]);
+            rpass.draw(0..4, 0..1);
+        }
+    }
+}
+
+
+// a sampled image from the scene (base color, normal map, ...), uploaded with its full mip chain
+pub struct ImageTexture {
+    pub texture: wgpu::Texture,
+    pub view: TextureView,
+    pub sampler: Sampler,
+}
+
+impl ImageTexture {
+    pub fn from_gltf(device: &Device, queue: &Queue, texture: &gltf::Texture, images: &[gltf::image::Data], srgb: bool) -> Self {
+        let image = &images[texture.source().index()];
+        let pixels = to_rgba8(image);
+        let sampler = texture.sampler();
+
+        let address_mode = |mode| match mode {
+            WrappingMode::ClampToEdge => AddressMode::ClampToEdge,
+            WrappingMode::MirroredRepeat => AddressMode::MirrorRepeat,
+            WrappingMode::Repeat => AddressMode::Repeat,
+        };
+        let (min_filter, mipmap_filter) = match sampler.min_filter() {
+            Some(MinFilter::Nearest) | Some(MinFilter::NearestMipmapNearest) => (FilterMode::Nearest, FilterMode::Nearest),
+            Some(MinFilter::NearestMipmapLinear) => (FilterMode::Nearest, FilterMode::Linear),
+            Some(MinFilter::Linear) | Some(MinFilter::LinearMipmapNearest) => (FilterMode::Linear, FilterMode::Nearest),
+            Some(MinFilter::LinearMipmapLinear) | None => (FilterMode::Linear, FilterMode::Linear),
+        };
+        let sampler_desc = SamplerDescriptor {
+            address_mode_u: address_mode(sampler.wrap_s()),
+            address_mode_v: address_mode(sampler.wrap_t()),
+            mag_filter: match sampler.mag_filter() {
+                Some(MagFilter::Nearest) => FilterMode::Nearest,
+                _ => FilterMode::Linear,
+            },
+            min_filter,
+            mipmap_filter,
+            ..Default::default()
+        };
+
+        Self::new(device, queue, image.width, image.height, pixels, srgb, &sampler_desc)
+    }
+
+    // 1x1 stand in for textures a material doesn't have
+    pub fn from_color(device: &Device, queue: &Queue, color: [u8; 4], srgb: bool) -> Self {
+        Self::new(device, queue, 1, 1, color.to_vec(), srgb, &SamplerDescriptor::default())
+    }
+
+    fn new(device: &Device, queue: &Queue, width: u32, height: u32, pixels: Vec<u8>, srgb: bool, sampler_desc: &SamplerDescriptor) -> Self {
+        let mips = mip_chain(width, height, pixels, srgb);
+
+        let texture = device.create_texture(&TextureDescriptor {
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: mips.len() as u32,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: if srgb { TextureFormat::Rgba8UnormSrgb } else { TextureFormat::Rgba8Unorm },
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            label: Some("image texture"),
+        });
+
+        for (i, (width, height, pixels)) in mips.iter().enumerate() {
+            queue.write_texture(
+                ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: i as u32,
+                    origin: Origin3d::ZERO,
+                    aspect: TextureAspect::All,
+                },
+                pixels,
+                ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(4 * width),
+                    rows_per_image: NonZeroU32::new(*height),
+                },
+                Extent3d {
+                    width: *width,
+                    height: *height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        let sampler = device.create_sampler(sampler_desc);
+
+        Self { texture, view, sampler }
+    }
+}
+
+fn to_rgba8(image: &gltf::image::Data) -> Vec<u8> {
+    // 16 bit formats keep their high byte
+    let (channels, bytes) = match image.format {
+        Format::R8 => (1, 1),
+        Format::R8G8 => (2, 1),
+        Format::R8G8B8 | Format::B8G8R8 => (3, 1),
+        Format::R8G8B8A8 | Format::B8G8R8A8 => (4, 1),
+        Format::R16 => (1, 2),
+        Format::R16G16 => (2, 2),
+        Format::R16G16B16 => (3, 2),
+        Format::R16G16B16A16 => (4, 2),
+    };
+    let bgr = matches!(image.format, Format::B8G8R8 | Format::B8G8R8A8);
+    image.pixels.chunks_exact(channels * bytes).flat_map(|pixel| {
+        let c = |i: usize| pixel[i * bytes + bytes - 1];
+        let mut rgba = match channels {
+            1 => [c(0), c(0), c(0), 255],
+            2 => [c(0), c(1), 0, 255],
+            3 => [c(0), c(1), c(2), 255],
+            _ => [c(0), c(1), c(2), c(3)],
+        };
+        if bgr {
+            rgba.swap(0, 2);
+        }
+        rgba
+    }).collect()
+}
+
+// box filtered mips down to 1x1, srgb images are averaged in linear space
+fn mip_chain(width: u32, height: u32, pixels: Vec<u8>, srgb: bool) -> Vec<(u32, u32, Vec<u8>)> {
+    let to_linear = |c: u8| {
+        let c = c as f32 / 255.0;
+        if srgb { if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) } } else { c }
+    };
+    let from_linear = |c: f32| {
+        let c = if srgb { if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 } } else { c };
+        (c * 255.0).round().clamp(0.0, 255.0) as u8
+    };
+
+    let mut mips = vec![(width, height, pixels)];
+    while let Some(&(w, h, ref prev)) = mips.last().filter(|(w, h, _)| *w > 1 || *h > 1) {
+        let (next_w, next_h) = ((w / 2).max(1), (h / 2).max(1));
+        let mut next = Vec::with_capacity((next_w * next_h * 4) as usize);
+        for y in 0..next_h {
+            for x in 0..next_w {
+                let texels = [(2 * x, 2 * y), (2 * x + 1, 2 * y), (2 * x, 2 * y + 1), (2 * x + 1, 2 * y + 1)]
+                    .map(|(tx, ty)| ((tx.min(w - 1) + ty.min(h - 1) * w) * 4) as usize);
+                for c in 0..4 {
+                    if c == 3 {
+                        let sum = texels.iter().map(|&t| prev[t + c] as f32 / 255.0).sum::<f32>();
+                        next.push((sum / 4.0 * 255.0).round() as u8);
+                    } else {
+                        let sum = texels.iter().map(|&t| to_linear(prev[t + c])).sum::<f32>();
+                        next.push(from_linear(sum / 4.0));
+                    }
+                }
+            }
+        }
+        mips.push((next_w, next_h, next));
+    }
+    mips
+}
@@ -1,13 +0,0 @@
-#![feature(array_chunks)]
-#![feature(map_first_last)]
-
-pub mod context;
-pub mod mesh;
-pub mod camera;
-pub mod texture;
-pub mod scene;
-pub mod light;
-pub mod material;
-pub mod sky;
-pub mod blur;
-pub mod animation;